@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use log;
+use serde_json;
+use rocket::{
+    fairing::{self, Info, Kind},
+    Request, Response, State,
+};
+
+use super::{
+    context::Context,
+    graphql::UID,
+    metrics,
+    request::{ClientIp, RequestId, Token},
+};
+
+// log4rs logger of the access log
+pub const TARGET: &'static str = "access";
+
+#[derive(Serialize, Debug)]
+struct Line {
+    request_id: Option<String>,
+    method: String,
+    path: String,
+    status: u16,
+    // milliseconds
+    duration: Option<f64>,
+    ip: Option<String>,
+    uid: Option<String>,
+    created_at: String,
+}
+
+fn uid(request: &Request) -> Option<String> {
+    if let Some(Token(Some(token))) = request.guard::<Token>().succeeded() {
+        if let Some(ctx) = request.guard::<State<Arc<Context>>>().succeeded() {
+            if let Ok(payload) = ctx.jwt.parse(&token) {
+                return payload
+                    .get(UID)
+                    .and_then(|v| v.as_str())
+                    .map(|v| v.to_string());
+            }
+        }
+    }
+    None
+}
+
+pub struct Fairing;
+
+impl fairing::Fairing for Fairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Access log",
+            kind: Kind::Response,
+        }
+    }
+
+    fn on_response(&self, request: &Request, response: &mut Response) {
+        let line = Line {
+            request_id: request.guard::<RequestId>().succeeded().map(|RequestId(v)| v),
+            method: request.method().to_string(),
+            path: request.uri().path().to_string(),
+            status: response.status().code,
+            duration: metrics::elapsed(request).map(|v| v * 1e3),
+            ip: request.guard::<ClientIp>().succeeded().map(|ClientIp(v)| v),
+            uid: uid(request),
+            created_at: Utc::now().to_rfc3339(),
+        };
+        match serde_json::to_string(&line) {
+            Ok(v) => log::info!(target: TARGET, "{}", v),
+            Err(e) => log::error!("{:?}", e),
+        }
+    }
+}
@@ -2,8 +2,17 @@ use log;
 
 use super::super::{context::Context, errors::Result};
 
+// the memory cache lives in the server process, this one would only see its own empty store
+fn open() -> Result<Context> {
+    let cfg = super::parse_config()?;
+    if cfg.cache.redis.is_none() {
+        return Err("the memory cache can't be reached from the command line, set cache.redis".into());
+    }
+    Context::new(&cfg)
+}
+
 pub fn list() -> Result<()> {
-    let ctx = Context::new(&super::parse_config()?)?;
+    let ctx = open()?;
     let items = ctx.cache.keys()?;
     println!("{:64} {}", "KEY", "TTL");
     for (key, ttl) in items {
@@ -13,7 +22,7 @@ pub fn list() -> Result<()> {
 }
 
 pub fn clear() -> Result<()> {
-    let ctx = Context::new(&super::parse_config()?)?;
+    let ctx = open()?;
     let cnt = ctx.cache.clear()?;
     log::info!("remove {} items from cache", cnt);
     Ok(())
@@ -1,9 +1,17 @@
+use std::fs::{self, File};
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 
-use chrono::NaiveDateTime;
-use diesel::prelude::*;
+use chrono::{NaiveDateTime, Utc};
+use diesel::{prelude::*, Connection};
+use log;
 
-use super::super::{context::Context, errors::Result, orm::schema::schema_migrations, rfc::RFC822};
+use super::super::{
+    context::Context,
+    errors::{Error, Result},
+    orm::{dump, migration, schema::schema_migrations},
+    rfc::RFC822,
+};
 
 pub fn versions() -> Result<()> {
     let ctx = Context::new(&super::parse_config()?)?;
@@ -21,3 +29,76 @@ pub fn versions() -> Result<()> {
 
     Ok(())
 }
+
+pub fn migrate() -> Result<()> {
+    let db = super::parse_config()?.database.open()?.get()?;
+    migration::migrate(db.deref())
+}
+
+pub fn rollback() -> Result<()> {
+    let db = super::parse_config()?.database.open()?.get()?;
+    if !migration::rollback(db.deref())? {
+        log::warn!("no migration to rollback");
+    }
+    Ok(())
+}
+
+pub fn status() -> Result<()> {
+    let db = super::parse_config()?.database.open()?.get()?;
+    println!("{:8} {:16} {:40} {}", "STATUS", "VERSION", "NAME", "RUN ON");
+    for (it, run_on) in migration::status(db.deref())? {
+        match run_on {
+            Some(v) => println!(
+                "{:8} {:16} {:40} {}",
+                "up",
+                it.version,
+                it.name,
+                v.to_rfc822()
+            ),
+            None => println!("{:8} {:16} {:40}", "down", it.version, it.name),
+        }
+    }
+    Ok(())
+}
+
+pub fn reset() -> Result<()> {
+    let db = super::parse_config()?.database.open()?.get()?;
+    migration::reset(db.deref())
+}
+
+pub fn dump(output: Option<&str>, format: &str) -> Result<()> {
+    let format = dump::Format::parse(format)?;
+    let output = match output {
+        Some(v) => Path::new(v).to_path_buf(),
+        None => {
+            let root = Path::new("backups");
+            if !root.exists() {
+                fs::create_dir_all(&root)?;
+            }
+            root.join(format!("{}.tar.gz", Utc::now().format("%Y%m%d%H%M%S")))
+        }
+    };
+    let db = super::parse_config()?.database.open()?.get()?;
+    let manifest = dump::dump(db.deref(), File::create(&output)?, &format)?;
+    log::info!(
+        "dump {} tables at version {} to {}",
+        manifest.tables.len(),
+        manifest.version,
+        output.display()
+    );
+    Ok(())
+}
+
+pub fn restore(input: PathBuf) -> Result<()> {
+    let db = super::parse_config()?.database.open()?.get()?;
+    let db = db.deref();
+    let manifest = db.transaction::<_, Error, _>(|| dump::restore(db, File::open(&input)?))?;
+    log::info!(
+        "restore {} tables from {}({}) at {}",
+        manifest.tables.len(),
+        manifest.driver,
+        manifest.version,
+        manifest.created_at.to_rfc822()
+    );
+    Ok(())
+}
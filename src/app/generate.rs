@@ -1,8 +1,10 @@
+use std::collections::BTreeMap;
 use std::env::current_dir;
 use std::fs;
 use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr};
 use std::os::unix::fs::OpenOptionsExt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use base64;
 use log;
@@ -10,20 +12,87 @@ use mustache;
 use rocket;
 use toml;
 
-use super::super::{cache, env, errors::Result, oauth, orm, queue, storage, utils};
+use super::super::{
+    cache, env, errors::Result, oauth, orm, queue,
+    rate_limit::{self, Key, Rule},
+    security, storage, utils,
+};
 
-pub fn nginx() -> Result<()> {
+pub struct Output {
+    path: Option<PathBuf>,
+    force: bool,
+}
+
+impl Output {
+    pub fn new(path: Option<&str>, force: bool) -> Self {
+        Self {
+            path: path.map(|v| Path::new(v).to_path_buf()),
+            force: force,
+        }
+    }
+
+    fn path(&self, default: PathBuf) -> PathBuf {
+        match self.path {
+            Some(ref v) => v.clone(),
+            None => default,
+        }
+    }
+
+    // fails on existing files unless --force is given
+    fn create(&self, file: &PathBuf, mode: u32) -> Result<fs::File> {
+        log::info!("generate file {}", file.display());
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut opt = fs::OpenOptions::new();
+        opt.write(true).mode(mode);
+        if self.force {
+            opt.create(true).truncate(true);
+        } else {
+            opt.create_new(true);
+        }
+        Ok(opt.open(file)?)
+    }
+}
+
+// Strict-Transport-Security is sent by the app, see http.headers.hsts
+pub struct Tls {
+    pub cert: String,
+    pub key: String,
+}
+
+pub struct Nginx {
+    // https on 443 with a redirect from 80 if set
+    pub tls: Option<Tls>,
+    // host:port of each backend, default is the local http server
+    pub upstreams: Vec<String>,
+    pub keepalive: u32,
+}
+
+pub fn nginx(out: &Output, opt: &Nginx) -> Result<()> {
     let tpl = mustache::compile_path(Path::new("templates").join("nginx.conf"))?;
     let cur = current_dir()?;
     let cfg = super::parse_config()?;
 
-    let file = Path::new("tmp").join("nginx.conf");
-    log::info!("generate file {}", file.display());
-    let mut fd = fs::OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .mode(0o644)
-        .open(file)?;
+    let upstreams = if opt.upstreams.is_empty() {
+        vec![format!("127.0.0.1:{}", cfg.http.port)]
+    } else {
+        opt.upstreams.clone()
+    };
+    let tls = match opt.tls {
+        Some(ref v) => {
+            if cfg.http.headers.as_ref().and_then(|h| h.hsts).is_none() {
+                log::warn!("https without Strict-Transport-Security, set http.headers.hsts");
+            }
+            json!({
+                "cert": v.cert,
+                "key": v.key,
+            })
+        }
+        None => json!(false),
+    };
+
+    let mut fd = out.create(&out.path(Path::new("tmp").join("nginx.conf")), 0o644)?;
     tpl.render(
         &mut fd,
         &json!({
@@ -31,12 +100,50 @@ pub fn nginx() -> Result<()> {
                 "port": cfg.http.port,
                 "root": cur,
                 "version":"v1",
+                "upstreams": upstreams,
+                "keepalive": opt.keepalive,
+                "body_size": cfg.http.upload_limits(),
+                "tls": tls,
             }),
     )?;
 
     Ok(())
 }
 
+// one unit for the http server and another for the queue worker
+pub fn systemd(out: &Output, user: &String) -> Result<()> {
+    let tpl = mustache::compile_path(Path::new("templates").join("systemd.service"))?;
+    let cur = current_dir()?;
+    let cfg = super::parse_config()?;
+    if cfg.queue.is_memory() {
+        return Err(super::MEMORY_QUEUE.into());
+    }
+    let dir = out.path(Path::new("tmp").to_path_buf());
+
+    for (name, command, description) in vec![
+        ("http", "http", "http server"),
+        ("worker", "worker", "queue worker and scheduler"),
+    ] {
+        let mut fd = out.create(
+            &dir.join(format!("{}-{}.service", cfg.name, name)),
+            0o644,
+        )?;
+        tpl.render(
+            &mut fd,
+            &json!({
+                "name": cfg.name,
+                "description": description,
+                "user": user,
+                "root": cur,
+                "exec": cur.join(env::NAME),
+                "command": command,
+            }),
+        )?;
+    }
+
+    Ok(())
+}
+
 pub fn config() -> Result<()> {
     let localhost = "localhost";
 
@@ -51,6 +158,41 @@ pub fn config() -> Result<()> {
             logging_level: format!("{}", rocket::config::LoggingLevel::Debug),
             port: 8080,
             limits: 1 << 16,
+            upload_limits: Some(1 << 24),
+            gzip_min_size: Some(1 << 10),
+            cache_control: Some(
+                vec![
+                    ("global", "public, max-age=86400"),
+                    ("assets", "public, max-age=3600"),
+                    ("3rd", "public, max-age=604800"),
+                    ("upload", "public, max-age=86400"),
+                ].iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            ),
+            cors: Some(security::Cors {
+                origins: vec![String::from("http://localhost:3000")],
+                methods: vec!["GET", "POST", "PATCH", "PUT", "DELETE"]
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect(),
+                headers: vec!["Authorization", "Accept", "Content-Type", "X-Request-Id"]
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect(),
+                credentials: true,
+            }),
+            headers: Some(security::Headers {
+                csp: Some(String::from(
+                    "default-src 'self'; img-src 'self' data: https:; style-src 'self' 'unsafe-inline'",
+                )),
+                hsts: None,
+                hsts_include_subdomains: None,
+                frame_options: Some(String::from("SAMEORIGIN")),
+                referrer_policy: Some(String::from("strict-origin-when-cross-origin")),
+            }),
+            // the nginx from generate:nginx
+            trusted_proxies: Some(vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))]),
         },
         oauth: oauth::Config {
             line: Some(oauth::line::Config {
@@ -75,6 +217,10 @@ pub fn config() -> Result<()> {
             user: String::from("root"),
             password: String::from(""),
         },
+        #[cfg(feature = "sqlite")]
+        database: orm::Config {
+            file: String::from("tmp/db"),
+        },
         cache: cache::Config {
             namespace: String::from("www.change-me.com"),
             redis: Some(cache::Redis {
@@ -83,6 +229,8 @@ pub fn config() -> Result<()> {
                 db: 6,
                 password: None,
             }),
+            // used when redis is not set, and by counters while redis is down
+            memory: Some(cache::Memory { capacity: 1 << 12 }),
         },
         queue: queue::Config {
             name: String::from("tasks"),
@@ -93,6 +241,20 @@ pub fn config() -> Result<()> {
                 user: String::from("guest"),
                 password: String::from("guest"),
             }),
+            // providers are tried in order: rabbitmq, database, memory
+            database: Some(queue::Database { interval: 5 }),
+            memory: Some(queue::Memory { capacity: 1 << 10 }),
+            retry: Some(queue::Retry {
+                max_attempts: 5,
+                backoff: 10,
+            }),
+            schedules: Some(vec![queue::Schedule {
+                name: String::from("purge-logs"),
+                cron: String::from("0 0 3 * * *"),
+                type_: String::from("purge-logs"),
+                priority: 1,
+                payload: Some(json!({ "days": 180 })),
+            }]),
         },
         aws: env::Aws {
             access_key_id: String::from("change-me"),
@@ -101,6 +263,7 @@ pub fn config() -> Result<()> {
         elasticsearch: env::ElasticSearch {
             hosts: vec![String::from("http://localhost:9200")],
         },
+        // nfs is used if both are set
         storage: storage::Config {
             nfs: Some(storage::Nfs {
                 end_point: String::from("/upload"),
@@ -109,8 +272,10 @@ pub fn config() -> Result<()> {
             s3: Some(storage::S3 {
                 region: String::from("us-west-2"),
                 bucket: String::from("www.change-me.com"),
+                endpoint: None,
             }),
         },
+        rate_limits: Some(rate_limits()),
     };
     let buf = toml::to_vec(&cfg)?;
 
@@ -125,3 +290,28 @@ pub fn config() -> Result<()> {
 
     Ok(())
 }
+
+fn rate_limits() -> BTreeMap<String, Rule> {
+    let mut items = BTreeMap::new();
+    for (action, limit, window, by) in vec![
+        (rate_limit::SIGN_IN, 10, 5 * 60, vec![Key::Ip, Key::Email]),
+        (rate_limit::SIGN_UP, 5, 60 * 60, vec![Key::Ip]),
+        (
+            rate_limit::FORGOT_PASSWORD,
+            5,
+            60 * 60,
+            vec![Key::Ip, Key::Email],
+        ),
+        (rate_limit::LEAVE_WORD, 10, 60 * 60, vec![Key::Ip]),
+    ] {
+        items.insert(
+            action.to_string(),
+            Rule {
+                limit: limit,
+                window: window,
+                by: by,
+            },
+        );
+    }
+    items
+}
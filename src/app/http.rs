@@ -1,9 +1,11 @@
 use std::sync::Arc;
 
 use rocket;
-use rocket_contrib::Template;
 
-use super::super::{context::Context, errors::Result, graphql, router};
+use super::super::{
+    access_log, compress, context::Context, errors::Result, graphql, metrics,
+    request::RequestIdFairing, router, security,
+};
 
 pub fn server(ctx: Arc<Context>) -> Result<()> {
     let mut app = rocket::custom(ctx.config.rocket()?, false);
@@ -17,8 +19,18 @@ pub fn server(ctx: Arc<Context>) -> Result<()> {
             graphql::query::Query,
             graphql::mutation::Mutation,
         ))
-        .attach(Template::fairing())
+        .attach(RequestIdFairing)
+        .attach(metrics::Fairing)
+        .attach(access_log::Fairing)
         .catch(router::catchers());
+    if let Some(ref cors) = ctx.config.http.cors {
+        app = app.attach(cors.fairing()?);
+    }
+    if let Some(ref headers) = ctx.config.http.headers {
+        app = app.attach(security::Fairing::new(headers));
+    }
+    // must be the last one, the others may read the plain body
+    app = app.attach(compress::Fairing::new(ctx.config.http.gzip_min_size()));
     Err(app.launch().into())
 }
 
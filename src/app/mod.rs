@@ -3,6 +3,9 @@ pub mod db;
 pub mod generate;
 pub mod http;
 pub mod i18n;
+pub mod queue;
+pub mod scheduler;
+pub mod storage;
 pub mod worker;
 
 use log;
@@ -18,22 +21,145 @@ use toml;
 
 use super::{context::Context, env, errors::Result};
 
+// tasks in it live and die with one process, so it can't be shared by separate commands
+const MEMORY_QUEUE: &'static str = "the memory queue only works inside a single process, set queue.database or queue.rabbitmq";
+
 pub fn main() -> Result<()> {
     sodiumoxide::init();
 
-    let generate_nginx = clap::SubCommand::with_name("generate:nginx").about("Generate nginx.conf");
+    let force = clap::Arg::with_name("force")
+        .long("force")
+        .help("Overwrite existing files");
+    let generate_nginx = clap::SubCommand::with_name("generate:nginx")
+        .about("Generate nginx.conf")
+        .arg(
+            clap::Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("FILE")
+                .help("Output file, default is tmp/nginx.conf")
+                .takes_value(true),
+        )
+        .arg(force.clone())
+        .arg(
+            clap::Arg::with_name("cert")
+                .long("ssl-certificate")
+                .value_name("FILE")
+                .help("Certificate chain, enables https and the http redirect")
+                .requires("key")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("key")
+                .long("ssl-certificate-key")
+                .value_name("FILE")
+                .help("Private key of the certificate")
+                .requires("cert")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("upstream")
+                .short("u")
+                .long("upstream")
+                .value_name("HOST:PORT")
+                .help("Backend servers, default is 127.0.0.1:<http.port>")
+                .multiple(true)
+                .number_of_values(1)
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("keepalive")
+                .long("keepalive")
+                .value_name("CONNECTIONS")
+                .help("Idle keepalive connections to the upstream servers")
+                .default_value("32")
+                .takes_value(true),
+        );
+    let generate_systemd = clap::SubCommand::with_name("generate:systemd")
+        .about("Generate systemd units of the http server and the worker")
+        .arg(
+            clap::Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("DIRECTORY")
+                .help("Output directory, default is tmp")
+                .takes_value(true),
+        )
+        .arg(force)
+        .arg(
+            clap::Arg::with_name("user")
+                .long("user")
+                .value_name("USER")
+                .help("Run as this user")
+                .default_value("www-data")
+                .takes_value(true),
+        );
     let generate_config =
         clap::SubCommand::with_name("generate:config").about("Generate config file");
 
     let db_versions = clap::SubCommand::with_name("db:versions")
         .about("Retrieves the current schema version number");
+    let db_migrate = clap::SubCommand::with_name("db:migrate").about("Run all pending migrations");
+    let db_rollback =
+        clap::SubCommand::with_name("db:rollback").about("Revert the latest migration");
+    let db_status =
+        clap::SubCommand::with_name("db:status").about("Show the status of all migrations");
+    let db_reset = clap::SubCommand::with_name("db:reset")
+        .about("Revert all migrations then run them again, ALL DATA WILL BE LOST");
+    let db_dump = clap::SubCommand::with_name("db:dump")
+        .about("Export all tables to a tar.gz archive")
+        .arg(
+            clap::Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("FILE")
+                .help("Archive file, default is backups/<timestamp>.tar.gz")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("format")
+                .short("f")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Format of each table")
+                .possible_values(&["json", "csv"])
+                .default_value("json")
+                .takes_value(true),
+        );
+    let db_restore = clap::SubCommand::with_name("db:restore")
+        .about("Import an archive from db:dump into an empty database")
+        .arg(
+            clap::Arg::with_name("input")
+                .value_name("FILE")
+                .help("Archive file")
+                .required(true)
+                .takes_value(true),
+        );
 
     let routes =
         clap::SubCommand::with_name("routes").about("Print out all defined routes in match order");
+    let http = clap::SubCommand::with_name("http").about("Start the http server only");
+    let worker =
+        clap::SubCommand::with_name("worker").about("Start the queue worker and scheduler only");
 
     let cache_clear = clap::SubCommand::with_name("cache:clear").about("Clear all cache items");
     let cache_list = clap::SubCommand::with_name("cache:list").about("List all cache items");
 
+    let queue_dead_letters =
+        clap::SubCommand::with_name("queue:dead-letters").about("List all dead letters");
+    let queue_retry = clap::SubCommand::with_name("queue:retry")
+        .about("Replay a dead letter")
+        .arg(
+            clap::Arg::with_name("id")
+                .value_name("ID")
+                .help("Dead letter's id")
+                .required(true)
+                .takes_value(true),
+        );
+
+    let storage_migrate = clap::SubCommand::with_name("storage:migrate")
+        .about("Copy all objects from the nfs local root into the s3 bucket");
+
     let i18n_sync = clap::SubCommand::with_name("i18n:sync")
         .about("Sync locales to database")
         .arg(
@@ -52,10 +178,22 @@ pub fn main() -> Result<()> {
         .before_help(env::BANNER)
         .after_help(env::HOMEPAGE)
         .subcommand(generate_nginx)
+        .subcommand(generate_systemd)
         .subcommand(generate_config)
+        .subcommand(http)
+        .subcommand(worker)
         .subcommand(db_versions)
+        .subcommand(db_migrate)
+        .subcommand(db_rollback)
+        .subcommand(db_status)
+        .subcommand(db_reset)
+        .subcommand(db_dump)
+        .subcommand(db_restore)
         .subcommand(cache_list)
         .subcommand(cache_clear)
+        .subcommand(queue_dead_letters)
+        .subcommand(queue_retry)
+        .subcommand(storage_migrate)
         .subcommand(routes)
         .subcommand(i18n_sync)
         .get_matches();
@@ -63,8 +201,31 @@ pub fn main() -> Result<()> {
     if let Some(_) = matches.subcommand_matches("generate:config") {
         return generate::config();
     }
-    if let Some(_) = matches.subcommand_matches("generate:nginx") {
-        return generate::nginx();
+    if let Some(matches) = matches.subcommand_matches("generate:nginx") {
+        let tls = match (matches.value_of("cert"), matches.value_of("key")) {
+            (Some(cert), Some(key)) => Some(generate::Tls {
+                cert: cert.to_string(),
+                key: key.to_string(),
+            }),
+            _ => None,
+        };
+        return generate::nginx(
+            &generate::Output::new(matches.value_of("output"), matches.is_present("force")),
+            &generate::Nginx {
+                tls: tls,
+                upstreams: match matches.values_of("upstream") {
+                    Some(v) => v.map(|x| x.to_string()).collect(),
+                    None => Vec::new(),
+                },
+                keepalive: matches.value_of("keepalive").unwrap_or("32").parse()?,
+            },
+        );
+    }
+    if let Some(matches) = matches.subcommand_matches("generate:systemd") {
+        return generate::systemd(
+            &generate::Output::new(matches.value_of("output"), matches.is_present("force")),
+            &matches.value_of("user").unwrap_or("www-data").to_string(),
+        );
     }
     if let Some(_) = matches.subcommand_matches("routes") {
         return http::routes();
@@ -72,12 +233,42 @@ pub fn main() -> Result<()> {
     if let Some(_) = matches.subcommand_matches("db:versions") {
         return db::versions();
     }
+    if let Some(_) = matches.subcommand_matches("db:migrate") {
+        return db::migrate();
+    }
+    if let Some(_) = matches.subcommand_matches("db:rollback") {
+        return db::rollback();
+    }
+    if let Some(_) = matches.subcommand_matches("db:status") {
+        return db::status();
+    }
+    if let Some(_) = matches.subcommand_matches("db:reset") {
+        return db::reset();
+    }
+    if let Some(matches) = matches.subcommand_matches("db:dump") {
+        let format = matches.value_of("format").unwrap_or("json");
+        return db::dump(matches.value_of("output"), format);
+    }
+    if let Some(matches) = matches.subcommand_matches("db:restore") {
+        let input = matches.value_of("input").unwrap_or("");
+        return db::restore(Path::new(input).to_path_buf());
+    }
     if let Some(_) = matches.subcommand_matches("cache:clear") {
         return cache::clear();
     }
     if let Some(_) = matches.subcommand_matches("cache:list") {
         return cache::list();
     }
+    if let Some(_) = matches.subcommand_matches("queue:dead-letters") {
+        return queue::dead_letters();
+    }
+    if let Some(matches) = matches.subcommand_matches("queue:retry") {
+        let id = matches.value_of("id").unwrap_or("");
+        return queue::retry(id.parse()?);
+    }
+    if let Some(_) = matches.subcommand_matches("storage:migrate") {
+        return storage::migrate();
+    }
     if let Some(_) = matches.subcommand_matches("i18n:sync") {
         let dir = matches.value_of("dir").unwrap_or("locales");
         return i18n::sync(Path::new(dir).to_path_buf());
@@ -88,6 +279,21 @@ pub fn main() -> Result<()> {
     let ctx = Context::new(&cfg)?;
     let ctx = Arc::new(ctx);
 
+    // the http server and the worker can run as separate services
+    if let Some(_) = matches.subcommand_matches("http") {
+        if cfg.queue.is_memory() {
+            return Err(MEMORY_QUEUE.into());
+        }
+        return http::server(Arc::clone(&ctx));
+    }
+    if let Some(_) = matches.subcommand_matches("worker") {
+        if cfg.queue.is_memory() {
+            return Err(MEMORY_QUEUE.into());
+        }
+        spawn_scheduler(&cfg, &ctx);
+        return worker::start(&cfg.queue, Arc::clone(&ctx));
+    }
+
     let que = cfg.queue.clone();
     let wrk = Arc::clone(&ctx);
     thread::spawn(move || match worker::start(&que, Arc::clone(&wrk)) {
@@ -95,9 +301,20 @@ pub fn main() -> Result<()> {
         Err(e) => log::error!("failed in worker: {:?}", e),
     });
 
+    spawn_scheduler(&cfg, &ctx);
+
     http::server(Arc::clone(&ctx))
 }
 
+fn spawn_scheduler(cfg: &env::Config, ctx: &Arc<Context>) {
+    let que = cfg.queue.clone();
+    let sch = Arc::clone(ctx);
+    thread::spawn(move || match scheduler::start(&que, Arc::clone(&sch)) {
+        Ok(_) => log::warn!("scheduler exit."),
+        Err(e) => log::error!("failed in scheduler: {:?}", e),
+    });
+}
+
 fn config_file() -> PathBuf {
     return Path::new("config.toml").to_path_buf();
 }
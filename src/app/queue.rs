@@ -0,0 +1,41 @@
+use std::ops::Deref;
+
+use log;
+
+use super::super::{context::Context, errors::Result, queue, rfc::RFC822};
+
+pub fn dead_letters() -> Result<()> {
+    let ctx = Context::new(&super::parse_config()?)?;
+    let db = ctx.db.get()?;
+    println!(
+        "{:8} {:16} {:8} {:32} {}",
+        "ID", "TYPE", "ATTEMPTS", "FAILED AT", "LAST ERROR"
+    );
+    for (id, type_, attempts, last_error, failed_at) in queue::dead_letters(db.deref())? {
+        println!(
+            "{:8} {:16} {:8} {:32} {}",
+            id,
+            type_,
+            attempts,
+            match failed_at {
+                Some(v) => v.to_rfc822(),
+                None => "".to_string(),
+            },
+            last_error.unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+pub fn retry(id: i64) -> Result<()> {
+    let cfg = super::parse_config()?;
+    // the task would be pushed into this process and lost when it exits
+    if cfg.queue.is_memory() {
+        return Err(super::MEMORY_QUEUE.into());
+    }
+    let ctx = Context::new(&cfg)?;
+    let db = ctx.db.get()?;
+    queue::retry(db.deref(), &ctx.producer, id)?;
+    log::info!("replay dead letter {}", id);
+    Ok(())
+}
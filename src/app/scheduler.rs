@@ -0,0 +1,71 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use cron::Schedule as Cron;
+use log;
+use mime;
+use serde_json;
+use uuid::Uuid;
+
+use super::super::{
+    context::Context,
+    errors::Result,
+    queue::{Config, Schedule},
+};
+
+pub fn start(cfg: &Config, ctx: Arc<Context>) -> Result<()> {
+    let mut jobs = Vec::new();
+    if let Some(ref items) = cfg.schedules {
+        for it in items {
+            let cron = match Cron::from_str(&it.cron) {
+                Ok(v) => v,
+                Err(e) => return Err(format!("bad cron {} for {}: {:?}", it.cron, it.name, e).into()),
+            };
+            log::info!("schedule job {} at {}", it.name, it.cron);
+            jobs.push((it, cron));
+        }
+    }
+    if jobs.is_empty() {
+        log::info!("no scheduled jobs");
+        return Ok(());
+    }
+    // the memory cache locks only this process, so every worker would fire the jobs;
+    // the memory queue is fine, it allows no other worker
+    if ctx.config.cache.redis.is_none() && !cfg.is_memory() {
+        return Err("scheduled jobs need cache.redis to be fired once by all the workers".into());
+    }
+
+    let mut last = Utc::now();
+    loop {
+        thread::sleep(StdDuration::from_secs(1));
+        let now = Utc::now();
+        for (it, cron) in jobs.iter() {
+            for at in cron.after(&last).take_while(|t| *t <= now) {
+                if let Err(e) = fire(&ctx, it, &at) {
+                    log::error!("fire job {} {:?}", it.name, e);
+                }
+            }
+        }
+        last = now;
+    }
+}
+
+// the redis lock makes sure only one worker process enqueues the job for each tick
+fn fire(ctx: &Context, it: &Schedule, at: &DateTime<Utc>) -> Result<()> {
+    let key = format!("scheduler://{}/{}", it.name, at.timestamp());
+    if !ctx.cache.lock(&key, Duration::hours(1))? {
+        log::debug!("job {} at {} is fired by others", it.name, at);
+        return Ok(());
+    }
+    log::info!("fire job {} at {}", it.name, at);
+    ctx.producer.push(
+        &Uuid::new_v4().to_string(),
+        &it.type_,
+        &format!("{}", mime::APPLICATION_JSON),
+        it.priority,
+        serde_json::to_vec(&it.payload)?.as_slice(),
+    )
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log;
+
+use super::super::{
+    errors::Result,
+    storage::{self, Provider},
+};
+
+// copy every object under the nfs local root into the s3 bucket, keeping the keys
+pub fn migrate() -> Result<()> {
+    let cfg = super::parse_config()?;
+    let nfs = match cfg.storage.nfs {
+        Some(v) => v,
+        None => return Err("nfs storage is not set".into()),
+    };
+    let bucket = match cfg.storage.s3 {
+        Some(ref v) => v.open(&cfg.aws)?,
+        None => return Err("s3 storage is not set".into()),
+    };
+
+    let mut keys = Vec::new();
+    walk(Path::new(&nfs.local_root), &PathBuf::new(), &mut keys)?;
+    let total = keys.len();
+    let mut copied = 0;
+    for key in keys {
+        if bucket.exists(&key)? {
+            log::info!("skip {}", key);
+            continue;
+        }
+        log::info!("copy {}", key);
+        let body = nfs.get(&key)?;
+        bucket.put(&key, &storage::content_type(&key, &body), body.as_slice())?;
+        copied = copied + 1;
+    }
+    log::info!("total {}, copy {}", total, copied);
+    Ok(())
+}
+
+fn walk(root: &Path, dir: &PathBuf, keys: &mut Vec<String>) -> Result<()> {
+    for it in fs::read_dir(root.join(dir))? {
+        let it = it?;
+        let key = dir.join(it.file_name());
+        if it.file_type()?.is_dir() {
+            walk(root, &key, keys)?;
+        } else if let Some(key) = key.to_str() {
+            keys.push(key.to_string());
+        }
+    }
+    Ok(())
+}
@@ -1,20 +1,26 @@
 use std::collections::HashMap;
+use std::ops::Deref;
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
 use amqp::Basic;
+use diesel::Connection;
 use log;
 
 use super::super::{
     context::Context,
-    errors::Result,
-    plugins::nut::consumers::send_mail,
-    queue::{Config, Consumer, BAD_PROVIDER},
+    errors::{Error, Result},
+    metrics::{METRICS, QUEUE_CONSUMED},
+    plugins::nut::consumers::{purge_logs, send_mail},
+    queue::{self, Config, Consumer, RabbitMQ, Retry, BAD_PROVIDER},
 };
 
 lazy_static! {
     static ref CONSUMERS: HashMap<&'static str, Box<Consumer>> = {
         let mut m: HashMap<&'static str, Box<Consumer>> = HashMap::new();
         m.insert(send_mail::NAME, Box::new(send_mail::Consumer {}));
+        m.insert(purge_logs::NAME, Box::new(purge_logs::Consumer {}));
         m
     };
 }
@@ -36,10 +42,79 @@ impl Worker {
         payload: &[u8],
     ) -> Result<()> {
         log::info!("receive message {}@{}", id, type_);
-        match CONSUMERS.get(&type_[..]) {
+        let ret = match CONSUMERS.get(&type_[..]) {
             Some(c) => c.consume(&self.ctx, id, content_type, priority, payload),
             None => Err(format!("can't find consumer for {}", type_).into()),
-        }
+        };
+        METRICS.inc(
+            QUEUE_CONSUMED,
+            &[
+                ("type", type_),
+                ("result", if ret.is_ok() { "ok" } else { "error" }),
+            ],
+        );
+        ret
+    }
+}
+
+// the retry counter carried by rabbitmq messages
+const ATTEMPTS: &'static str = "x-attempts";
+
+impl Worker {
+    fn bury(
+        &self,
+        type_: &String,
+        content_type: &String,
+        priority: u8,
+        payload: &[u8],
+        attempts: i16,
+        error: &String,
+    ) -> Result<()> {
+        let db = self.ctx.db.get()?;
+        let id = queue::bury(
+            db.deref(),
+            type_,
+            content_type,
+            priority,
+            payload,
+            attempts,
+            error,
+        )?;
+        log::warn!(
+            "move message {} to dead letters as {} after {} attempts",
+            type_,
+            id,
+            attempts
+        );
+        Ok(())
+    }
+
+    fn retry(
+        &self,
+        channel: &mut amqp::Channel,
+        headers: &amqp::protocol::basic::BasicProperties,
+        body: &Vec<u8>,
+        attempts: i16,
+        retry: &Retry,
+    ) -> Result<()> {
+        let mut args = amqp::Table::new();
+        args.insert(
+            ATTEMPTS.to_string(),
+            amqp::TableEntry::LongInt(attempts as i32),
+        );
+        channel.basic_publish(
+            "",
+            &RabbitMQ::retry_queue(&self.ctx.config.queue.name)[..],
+            true,
+            false,
+            amqp::protocol::basic::BasicProperties {
+                expiration: Some(format!("{}", retry.delay(attempts).num_milliseconds())),
+                headers: Some(args),
+                ..headers.clone()
+            },
+            body.clone(),
+        )?;
+        Ok(())
     }
 }
 
@@ -51,28 +126,88 @@ impl amqp::Consumer for Worker {
         headers: amqp::protocol::basic::BasicProperties,
         body: Vec<u8>,
     ) {
-        if let Some(ref type_) = headers._type {
-            if let Some(ref content_type) = headers.content_type {
-                if let Some(ref id) = headers.message_id {
-                    if let Some(priority) = headers.priority {
-                        match self.consume(&id, &type_, &content_type, priority, body.as_slice()) {
-                            Ok(_) => if let Err(e) = channel.basic_ack(deliver.delivery_tag, false)
-                            {
-                                log::error!("ack {:?}", e);
-                            },
-                            Err(e) => log::error!("consume {:?}", e),
-                        };
-                        return;
+        let retry = self.ctx.config.queue.retry();
+        let attempts = match headers.headers {
+            Some(ref it) => match it.get(ATTEMPTS) {
+                Some(&amqp::TableEntry::LongInt(v)) => v as i16,
+                _ => 0,
+            },
+            None => 0,
+        };
+
+        let ret = match (
+            &headers._type,
+            &headers.content_type,
+            &headers.message_id,
+            headers.priority,
+        ) {
+            (Some(type_), Some(content_type), Some(id), Some(priority)) => {
+                match self.consume(&id, &type_, &content_type, priority, body.as_slice()) {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        log::error!("consume {:?}", e);
+                        let attempts = attempts + 1;
+                        let error = format!("{:?}", e);
+                        if retry.exhausted(attempts) {
+                            self.bury(
+                                type_,
+                                content_type,
+                                priority,
+                                body.as_slice(),
+                                attempts,
+                                &error,
+                            )
+                        } else if let Err(e) =
+                            self.retry(channel, &headers, &body, attempts, &retry)
+                        {
+                            log::error!("retry {:?}", e);
+                            self.bury(
+                                type_,
+                                content_type,
+                                priority,
+                                body.as_slice(),
+                                attempts,
+                                &error,
+                            )
+                        } else {
+                            Ok(())
+                        }
                     }
                 }
             }
+            _ => {
+                log::error!("bad task message header: {:?}", headers);
+                self.bury(
+                    &headers._type.clone().unwrap_or_default(),
+                    &headers.content_type.clone().unwrap_or_default(),
+                    headers.priority.unwrap_or(0),
+                    body.as_slice(),
+                    attempts,
+                    &format!("bad task message header: {:?}", headers),
+                )
+            }
+        };
+
+        // while the database is down, come back after the backoff rather than at once
+        if let Err(e) = ret {
+            log::error!("bury message {:?}", e);
+            if let Err(e) = self.retry(channel, &headers, &body, attempts + 1, &retry) {
+                log::error!("retry {:?}", e);
+                if let Err(e) = channel.basic_nack(deliver.delivery_tag, false, true) {
+                    log::error!("nack {:?}", e);
+                }
+                return;
+            }
+        }
+        if let Err(e) = channel.basic_ack(deliver.delivery_tag, false) {
+            log::error!("ack {:?}", e);
         }
-        log::error!("bad task message header: {:?}", headers);
     }
 }
 
 pub fn start(cfg: &Config, ctx: Arc<Context>) -> Result<()> {
     let name = cfg.name.clone();
+    let retry = cfg.retry();
     if let Some(ref cfg) = cfg.rabbitmq {
         return cfg.open(name, move |ch, qu| -> Result<()> {
             let worker = Worker::new(Arc::clone(&ctx));
@@ -91,5 +226,80 @@ pub fn start(cfg: &Config, ctx: Arc<Context>) -> Result<()> {
             Ok(())
         });
     }
+    if let Some(ref cfg) = cfg.database {
+        let worker = Worker::new(Arc::clone(&ctx));
+        log::info!("starting database consumer {}", name);
+        loop {
+            match poll(&worker, &retry) {
+                Ok(true) => {}
+                Ok(false) => thread::sleep(Duration::from_secs(cfg.interval)),
+                Err(e) => {
+                    log::error!("poll jobs {:?}", e);
+                    thread::sleep(Duration::from_secs(cfg.interval));
+                }
+            }
+        }
+    }
+    if let Some(_) = cfg.memory {
+        let worker = Worker::new(Arc::clone(&ctx));
+        log::info!("starting in-process consumer {}", name);
+        loop {
+            let mut it = ctx.producer.pop()?;
+            if let Err(e) = worker.consume(
+                &it.id,
+                &it.type_,
+                &it.content_type,
+                it.priority,
+                it.payload.as_slice(),
+            ) {
+                log::error!("consume {:?}", e);
+                it.attempts += 1;
+                let error = format!("{:?}", e);
+                if retry.exhausted(it.attempts) {
+                    match worker.bury(
+                        &it.type_,
+                        &it.content_type,
+                        it.priority,
+                        it.payload.as_slice(),
+                        it.attempts,
+                        &error,
+                    ) {
+                        Ok(_) => continue,
+                        // try again later rather than lose the task
+                        Err(e) => log::error!("bury message {:?}", e),
+                    }
+                }
+                if let Err(e) = ctx.producer.requeue(&it, retry.delay(it.attempts)) {
+                    log::error!("requeue {} {:?}", it.id, e);
+                }
+            }
+        }
+    }
     Err(BAD_PROVIDER.into())
 }
+
+// consume the next ready job, return false if there is nothing to do
+fn poll(worker: &Worker, retry: &Retry) -> Result<bool> {
+    let db = worker.ctx.db.get()?;
+    let db = db.deref();
+    db.transaction::<_, Error, _>(|| {
+        let it = match queue::fetch(db)? {
+            Some(v) => v,
+            None => return Ok(false),
+        };
+        match worker.consume(
+            &it.id.to_string(),
+            &it.type_,
+            &it.content_type,
+            it.priority as u8,
+            it.payload.as_slice(),
+        ) {
+            Ok(_) => queue::done(db, it.id)?,
+            Err(e) => {
+                log::error!("consume job {} {:?}", it.id, e);
+                queue::fail(db, it.id, it.attempts + 1, &format!("{:?}", e), retry)?;
+            }
+        };
+        Ok(true)
+    })
+}
@@ -1,13 +1,18 @@
+use std::collections::{BTreeMap, HashMap};
 use std::ops::Deref;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration as StdDuration, Instant};
 
 use chrono::Duration;
+use log;
 use r2d2::Pool;
 use r2d2_redis::RedisConnectionManager;
-use redis::{cmd, ConnectionAddr, ConnectionInfo};
+use redis::{cmd, pipe, ConnectionAddr, ConnectionInfo};
 use serde::{de::DeserializeOwned, ser::Serialize};
 use serde_json;
 
 use super::errors::Result;
+use super::metrics::{CACHE_ERRORS, CACHE_HITS, CACHE_MISSES, METRICS};
 
 const BAD_PROVIDER: &'static str = "bad cache provider";
 
@@ -19,9 +24,11 @@ where
 {
     if let Ok(buf) = ch.get(key) {
         if let Ok(val) = serde_json::from_slice(buf.as_slice()) {
+            METRICS.inc(CACHE_HITS, &[]);
             return Ok(val);
         }
     }
+    METRICS.inc(CACHE_MISSES, &[]);
     let val = fun()?;
     ch.set(
         key,
@@ -35,6 +42,13 @@ where
 pub struct Config {
     pub namespace: String,
     pub redis: Option<Redis>,
+    pub memory: Option<Memory>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Memory {
+    // max items to keep, the least recently used ones will be evicted
+    pub capacity: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -50,6 +64,7 @@ impl Config {
         let mut it = Cache {
             namespace: self.namespace.clone(),
             redis: None,
+            memory: None,
         };
         if let Some(ref cfg) = self.redis {
             it.redis = Some(Pool::new(RedisConnectionManager::new(ConnectionInfo {
@@ -57,6 +72,8 @@ impl Config {
                 db: cfg.db,
                 passwd: cfg.password.clone(),
             })?)?);
+        } else if let Some(ref cfg) = self.memory {
+            it.memory = Some(Mutex::new(Lru::new(cfg.capacity)));
         }
         Ok(it)
     }
@@ -65,6 +82,7 @@ impl Config {
 pub struct Cache {
     namespace: String,
     redis: Option<Pool<RedisConnectionManager>>,
+    memory: Option<Mutex<Lru>>,
 }
 
 impl Cache {
@@ -85,6 +103,10 @@ impl Cache {
             }
             return Ok(items);
         }
+        if let Some(ref lru) = self.memory {
+            let mut lru = lock(lru)?;
+            return Ok(lru.keys());
+        }
         Err(BAD_PROVIDER.into())
     }
 
@@ -95,6 +117,13 @@ impl Cache {
             let con = con.deref();
             return Ok(cmd("get").arg(&key).query::<Vec<u8>>(con)?);
         }
+        if let Some(ref lru) = self.memory {
+            let mut lru = lock(lru)?;
+            return match lru.get(&key) {
+                Some(v) => Ok(v),
+                None => Err(format!("can't find cache item {}", key).into()),
+            };
+        }
         Err(BAD_PROVIDER.into())
     }
     pub fn set(&self, key: &String, val: &[u8], ttl: Duration) -> Result<()> {
@@ -110,6 +139,93 @@ impl Cache {
                 .query(con)?;
             return Ok(());
         }
+        if let Some(ref lru) = self.memory {
+            let mut lru = lock(lru)?;
+            lru.set(key, val.to_vec(), ttl);
+            return Ok(());
+        }
+        Err(BAD_PROVIDER.into())
+    }
+
+    // set the key only if it does not exist, return false when it is held by others
+    pub fn lock(&self, key: &String, ttl: Duration) -> Result<bool> {
+        let key = self.key(key);
+        if let Some(ref pool) = self.redis {
+            let con = pool.get()?;
+            let con = con.deref();
+            let ok: Option<String> = cmd("set")
+                .arg(&key)
+                .arg(1)
+                .arg("nx")
+                .arg("ex")
+                .arg(ttl.num_seconds())
+                .query(con)?;
+            return Ok(ok.is_some());
+        }
+        if let Some(ref lru) = self.memory {
+            let mut lru = lock(lru)?;
+            if lru.get(&key).is_some() {
+                return Ok(false);
+            }
+            lru.set(key, vec![1], ttl);
+            return Ok(true);
+        }
+        Err(BAD_PROVIDER.into())
+    }
+
+    // increase the counter, the ttl is set by the first hit
+    pub fn incr(&self, key: &String, ttl: Duration) -> Result<i64> {
+        let key = self.key(key);
+        if let Some(ref pool) = self.redis {
+            return match Self::redis_incr(pool, &key, &ttl) {
+                Ok(v) => Ok(v),
+                Err(e) => {
+                    log::error!("incr {} by redis {:?}", key, e);
+                    METRICS.inc(CACHE_ERRORS, &[("command", "incr")]);
+                    Err(e)
+                }
+            };
+        }
+        if let Some(ref lru) = self.memory {
+            let mut lru = lock(lru)?;
+            return Ok(lru.incr(key, ttl));
+        }
+        Err(BAD_PROVIDER.into())
+    }
+
+    // in one transaction, so the counter never lives without a ttl
+    fn redis_incr(
+        pool: &Pool<RedisConnectionManager>,
+        key: &String,
+        ttl: &Duration,
+    ) -> Result<i64> {
+        let con = pool.get()?;
+        let (cnt,): (i64,) = pipe()
+            .atomic()
+            .cmd("set")
+            .arg(key)
+            .arg(0)
+            .arg("nx")
+            .arg("ex")
+            .arg(ttl.num_seconds())
+            .ignore()
+            .cmd("incr")
+            .arg(key)
+            .query(con.deref())?;
+        Ok(cnt)
+    }
+
+    pub fn ping(&self) -> Result<()> {
+        if let Some(ref pool) = self.redis {
+            let con = pool.get()?;
+            let con = con.deref();
+            let _: String = cmd("ping").query(con)?;
+            return Ok(());
+        }
+        if let Some(ref lru) = self.memory {
+            lock(lru)?;
+            return Ok(());
+        }
         Err(BAD_PROVIDER.into())
     }
 
@@ -126,6 +242,159 @@ impl Cache {
             }
             return Ok(0);
         }
+        if let Some(ref lru) = self.memory {
+            let mut lru = lock(lru)?;
+            return Ok(lru.clear(&format!("{}://", self.namespace)));
+        }
         Err(BAD_PROVIDER.into())
     }
 }
+
+fn lock(lru: &Mutex<Lru>) -> Result<MutexGuard<Lru>> {
+    match lru.lock() {
+        Ok(v) => Ok(v),
+        Err(e) => Err(format!("{}", e).into()),
+    }
+}
+
+struct Item {
+    value: Vec<u8>,
+    expire: Instant,
+    tick: u64,
+}
+
+// in-process least-recently-used store with per-key ttl
+struct Lru {
+    capacity: usize,
+    tick: u64,
+    items: HashMap<String, Item>,
+    // access tick => key, the first one is the least recently used
+    order: BTreeMap<u64, String>,
+}
+
+impl Lru {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity,
+            tick: 0,
+            items: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn touch(&mut self, key: &String) {
+        self.tick += 1;
+        if let Some(it) = self.items.get_mut(key) {
+            self.order.remove(&it.tick);
+            it.tick = self.tick;
+            self.order.insert(self.tick, key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &String) -> bool {
+        if let Some(it) = self.items.remove(key) {
+            self.order.remove(&it.tick);
+            return true;
+        }
+        false
+    }
+
+    fn purge(&mut self) {
+        let now = Instant::now();
+        let keys: Vec<String> = self
+            .items
+            .iter()
+            .filter(|(_, v)| v.expire <= now)
+            .map(|(k, _)| k.clone())
+            .collect();
+        for k in keys {
+            self.remove(&k);
+        }
+    }
+
+    fn get(&mut self, key: &String) -> Option<Vec<u8>> {
+        let alive = match self.items.get(key) {
+            Some(it) => it.expire > Instant::now(),
+            None => return None,
+        };
+        if !alive {
+            self.remove(key);
+            return None;
+        }
+        self.touch(key);
+        self.items.get(key).map(|it| it.value.clone())
+    }
+
+    // expired items are dropped by get, or evicted from the front when it is full
+    fn set(&mut self, key: String, value: Vec<u8>, ttl: Duration) {
+        self.remove(&key);
+        while self.capacity > 0 && self.items.len() >= self.capacity {
+            let oldest = match self.order.iter().next() {
+                Some((_, k)) => k.clone(),
+                None => break,
+            };
+            self.remove(&oldest);
+        }
+        self.tick += 1;
+        let ttl = match ttl.to_std() {
+            Ok(v) => v,
+            Err(_) => StdDuration::from_secs(0),
+        };
+        self.order.insert(self.tick, key.clone());
+        self.items.insert(
+            key,
+            Item {
+                value: value,
+                expire: Instant::now() + ttl,
+                tick: self.tick,
+            },
+        );
+    }
+
+    fn incr(&mut self, key: String, ttl: Duration) -> i64 {
+        let cnt = match self.get(&key) {
+            Some(v) => match String::from_utf8(v) {
+                Ok(v) => v.parse::<i64>().unwrap_or(0),
+                Err(_) => 0,
+            },
+            None => {
+                self.set(key, b"1".to_vec(), ttl);
+                return 1;
+            }
+        } + 1;
+        // keep the expire time of the first hit
+        if let Some(it) = self.items.get_mut(&key) {
+            it.value = cnt.to_string().into_bytes();
+        }
+        cnt
+    }
+
+    fn keys(&mut self) -> Vec<(String, isize)> {
+        self.purge();
+        let now = Instant::now();
+        let mut items: Vec<(String, isize)> = self
+            .items
+            .iter()
+            .map(|(k, v)| (k.clone(), v.expire.duration_since(now).as_secs() as isize))
+            .collect();
+        items.sort();
+        items
+    }
+
+    fn clear(&mut self, prefix: &String) -> isize {
+        self.purge();
+        let keys: Vec<String> = self
+            .items
+            .keys()
+            .filter(|k| k.starts_with(prefix))
+            .cloned()
+            .collect();
+        let mut cnt = 0;
+        for k in keys {
+            if self.remove(&k) {
+                cnt += 1;
+            }
+        }
+        cnt
+    }
+}
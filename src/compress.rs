@@ -0,0 +1,87 @@
+use std::io::{self, Cursor, Write};
+
+use flate2::{write::GzEncoder, Compression};
+use rocket::{
+    fairing::{self, Info, Kind},
+    http::{ContentType, Header},
+    Request, Response,
+};
+
+pub fn gzip(buf: &[u8]) -> io::Result<Vec<u8>> {
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+    enc.write_all(buf)?;
+    enc.finish()
+}
+
+// an explicit gzip entry wins over "*", eg. "*;q=0, gzip" allows it, "gzip;q=0, *" does not
+pub fn accept_gzip(request: &Request) -> bool {
+    let mut any = None;
+    for line in request.headers().get("Accept-Encoding") {
+        for it in line.split(',') {
+            let mut it = it.split(';');
+            let name = it.next().map(|v| v.trim()).unwrap_or("");
+            if name != "gzip" && name != "*" {
+                continue;
+            }
+            let disabled = it.any(|v| {
+                let v = v.trim();
+                v.starts_with("q=") && v[2..].parse::<f32>().map(|q| q <= 0.0).unwrap_or(false)
+            });
+            if name == "gzip" {
+                return !disabled;
+            }
+            any = Some(!disabled);
+        }
+    }
+    any.unwrap_or(false)
+}
+
+fn compressible(ct: &ContentType) -> bool {
+    ct.is_json() || ct.is_html() || ct.is_xml() || ct.sub().as_str().ends_with("+xml")
+}
+
+pub struct Fairing {
+    // bytes, smaller bodies are not worth the cpu
+    min_size: usize,
+}
+
+impl Fairing {
+    pub fn new(min_size: usize) -> Self {
+        Self { min_size: min_size }
+    }
+}
+
+impl fairing::Fairing for Fairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Gzip compression",
+            kind: Kind::Response,
+        }
+    }
+
+    fn on_response(&self, request: &Request, response: &mut Response) {
+        if response.headers().contains("Content-Encoding") || !accept_gzip(request) {
+            return;
+        }
+        match response.content_type() {
+            Some(ref ct) if compressible(ct) => {}
+            _ => return,
+        }
+        let body = match response.body_bytes() {
+            Some(v) => v,
+            None => return,
+        };
+        if body.len() < self.min_size {
+            response.set_sized_body(Cursor::new(body));
+            return;
+        }
+        match gzip(&body) {
+            Ok(buf) => {
+                response.set_header(Header::new("Content-Encoding", "gzip"));
+                response.set_sized_body(Cursor::new(buf));
+            }
+            Err(_) => response.set_sized_body(Cursor::new(body)),
+        }
+        response.adjoin_header(Header::new("Vary", "Accept-Encoding"));
+    }
+}
@@ -2,7 +2,7 @@ use frank_jwt::Algorithm;
 
 use super::{
     cache::Cache, env::Config, errors::Result, jwt::Jwt, orm, queue::Producer, storage::Storage,
-    utils::Encryptor,
+    theme::Themes, utils::Encryptor,
 };
 
 pub struct Context {
@@ -13,18 +13,21 @@ pub struct Context {
     pub encryptor: Encryptor,
     pub config: Config,
     pub jwt: Jwt,
+    pub themes: Themes,
 }
 
 impl Context {
     pub fn new(cfg: &Config) -> Result<Self> {
+        let db = cfg.database.open()?;
         Ok(Self {
-            db: cfg.database.open()?,
+            producer: Producer::new(cfg.queue.clone(), db.clone()),
+            db: db,
             cache: cfg.cache.open()?,
-            producer: Producer::new(cfg.queue.clone()),
             encryptor: Encryptor::new(cfg.secret_key()?.as_slice())?,
-            storage: Storage::new(cfg.storage.clone()),
+            storage: Storage::new(cfg.storage.clone(), &cfg.aws)?,
             jwt: Jwt::new(cfg.secret_key.clone(), Algorithm::HS512),
             config: cfg.clone(),
+            themes: Themes::new(),
         })
     }
 }
@@ -1,9 +1,10 @@
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
 
 use base64;
 use rocket;
 
-use super::{cache, errors::Result, oauth, orm, queue, storage};
+use super::{cache, errors::Result, oauth, orm, queue, rate_limit, security, storage};
 
 #[cfg(not(debug_assertions))]
 pub fn version() -> String {
@@ -43,6 +44,9 @@ pub struct Config {
     pub storage: storage::Config,
     pub elasticsearch: ElasticSearch,
     pub aws: Aws,
+    // action => rule
+    #[serde(rename = "ratelimits")]
+    pub rate_limits: Option<BTreeMap<String, rate_limit::Rule>>,
 }
 
 impl Config {
@@ -75,17 +79,6 @@ impl Config {
                     .limit("forms", self.http.limits)
                     .limit("json", self.http.limits),
             )
-            .extra(
-                "template_dir",
-                match Path::new("themes")
-                    .join(self.http.theme.clone())
-                    .join("views")
-                    .to_str()
-                {
-                    Some(v) => v,
-                    None => "views",
-                },
-            )
             .finalize()?)
     }
 }
@@ -96,8 +89,41 @@ pub struct Http {
     pub workers: u16,
     //  one of "normal", "debug", or "critical"
     pub logging_level: String,
+    // default theme, until one is picked by the admin
     pub theme: String,
     pub limits: u64,
+    // max bytes of each uploaded file
+    #[serde(rename = "uploadlimits")]
+    pub upload_limits: Option<u64>,
+    // min bytes of a response to be gzipped
+    #[serde(rename = "gzipminsize")]
+    pub gzip_min_size: Option<usize>,
+    // folder => Cache-Control of its static files, eg. assets = "public, max-age=86400"
+    #[serde(rename = "cachecontrol")]
+    pub cache_control: Option<BTreeMap<String, String>>,
+    pub cors: Option<security::Cors>,
+    pub headers: Option<security::Headers>,
+    // reverse proxies whose X-Real-IP and X-Forwarded-For are believed, eg. ["127.0.0.1"]
+    #[serde(rename = "trustedproxies")]
+    pub trusted_proxies: Option<Vec<IpAddr>>,
+}
+
+impl Http {
+    pub fn trusted_proxies(&self) -> Vec<IpAddr> {
+        self.trusted_proxies.clone().unwrap_or_default()
+    }
+    pub fn upload_limits(&self) -> u64 {
+        self.upload_limits.unwrap_or(1 << 24)
+    }
+    pub fn gzip_min_size(&self) -> usize {
+        self.gzip_min_size.unwrap_or(1 << 10)
+    }
+    pub fn cache_control(&self, dir: &str) -> Option<String> {
+        self.cache_control
+            .as_ref()
+            .and_then(|v| v.get(dir))
+            .cloned()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
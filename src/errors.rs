@@ -1,11 +1,13 @@
 error_chain!{
     foreign_links {
         StdIo(::std::io::Error);
+        StdFmt(::std::fmt::Error);
         StdSystemTime(::std::time::SystemTimeError);
         StdStrUtf8(::std::str::Utf8Error);
         StdNumParseInt(::std::num::ParseIntError);
 
         SerdeJson(::serde_json::Error);
+        Csv(::csv::Error);
         Redis(::redis::RedisError);
         R2d2(::r2d2::Error);
         ChronoParse(::chrono::ParseError);
@@ -9,7 +9,8 @@ use rocket::http::Status;
 use super::super::{
     context::Context as AppContext,
     errors::Result,
-    orm::{schema::users, PooledConnection as Db},
+    jwt::Jwt,
+    orm::{schema::users, Connection, PooledConnection as Db},
     plugins::nut::{
         dao::policy as policy_dao, graphql::users::mutation::ACT_SIGN_IN, models::Role,
     },
@@ -26,6 +27,7 @@ pub struct Context {
     pub locale: String,
     pub token: Option<String>,
     pub client_ip: String,
+    pub request_id: String,
     pub app: Arc<AppContext>,
     pub db: Db,
 }
@@ -35,44 +37,7 @@ impl juniper::Context for Context {}
 impl Context {
     pub fn current_user(&self) -> Result<CurrentUser> {
         if let Some(ref token) = self.token {
-            let payload = self.app.jwt.parse(token)?;
-            if let Some(uid) = payload.get(super::UID.to_string()) {
-                if let Some(act) = payload.get(super::ACT.to_string()) {
-                    if act == ACT_SIGN_IN {
-                        if let Some(uid) = uid.as_str() {
-                            let uid = uid.to_string();
-                            let db = self.db.deref();
-                            let (id, email, confirmed_at, locked_at) = users::dsl::users
-                                .select((
-                                    users::dsl::id,
-                                    users::dsl::email,
-                                    users::dsl::confirmed_at,
-                                    users::dsl::locked_at,
-                                ))
-                                .filter(users::dsl::uid.eq(&uid))
-                                .first::<(i64, String, Option<NaiveDateTime>, Option<NaiveDateTime>)>(
-                                    db,
-                                )?;
-                            // check is confirm
-                            if None == confirmed_at {
-                                return Err(
-                                    t!(db, &self.locale, "nut.errors.user.not-confirmed").into()
-                                );
-                            }
-                            // check is not lock
-                            if let Some(_) = locked_at {
-                                return Err(t!(db, &self.locale, "nut.errors.user.is-locked").into());
-                            }
-                            return Ok(CurrentUser {
-                                id: id,
-                                uid: uid,
-                                email: email,
-                            });
-                        }
-                    }
-                }
-            }
-            return Err(Status::Unauthorized.reason.into());
+            return current_user(self.db.deref(), &self.app.jwt, &self.locale, token);
         }
         Err(Status::NonAuthoritativeInformation.reason.into())
     }
@@ -85,7 +50,59 @@ impl Context {
         Err(Status::Forbidden.reason.into())
     }
 
+    // count a hit of the action, fails with "too many requests" once over the limit
+    pub fn rate_limit(&self, action: &str, email: Option<&String>) -> Result<()> {
+        if let Some(ref rules) = self.app.config.rate_limits {
+            if let Some(rule) = rules.get(action) {
+                if !rule.hit(&self.app.cache, action, &self.client_ip, email)? {
+                    return Err(t!(
+                        self.db.deref(),
+                        &self.locale,
+                        "nut.errors.too-many-requests"
+                    ).into());
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn client_ip(&self) -> Result<String> {
         Ok("ip".to_string())
     }
 }
+
+pub fn current_user(db: &Connection, jwt: &Jwt, locale: &String, token: &String) -> Result<CurrentUser> {
+    let payload = jwt.parse(token)?;
+    if let Some(uid) = payload.get(super::UID.to_string()) {
+        if let Some(act) = payload.get(super::ACT.to_string()) {
+            if act == ACT_SIGN_IN {
+                if let Some(uid) = uid.as_str() {
+                    let uid = uid.to_string();
+                    let (id, email, confirmed_at, locked_at) = users::dsl::users
+                        .select((
+                            users::dsl::id,
+                            users::dsl::email,
+                            users::dsl::confirmed_at,
+                            users::dsl::locked_at,
+                        ))
+                        .filter(users::dsl::uid.eq(&uid))
+                        .first::<(i64, String, Option<NaiveDateTime>, Option<NaiveDateTime>)>(db)?;
+                    // check is confirm
+                    if None == confirmed_at {
+                        return Err(t!(db, locale, "nut.errors.user.not-confirmed").into());
+                    }
+                    // check is not lock
+                    if let Some(_) = locked_at {
+                        return Err(t!(db, locale, "nut.errors.user.is-locked").into());
+                    }
+                    return Ok(CurrentUser {
+                        id: id,
+                        uid: uid,
+                        email: email,
+                    });
+                }
+            }
+        }
+    }
+    Err(Status::Unauthorized.reason.into())
+}
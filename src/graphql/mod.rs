@@ -1,7 +1,12 @@
 macro_rules! gq {
-    ($x:expr, $y:expr) => {
-        ge!($y.call(&$x.context()))
-    };
+    ($x:expr, $y:expr) => {{
+        let ctx = $x.context();
+        let ret = $y.call(&ctx);
+        if let Err(ref e) = ret {
+            log::error!("{} {:?}", ctx.request_id, e);
+        }
+        ge!(ret)
+    }};
 }
 
 macro_rules! ge {
@@ -18,7 +23,6 @@ pub mod mutation;
 pub mod query;
 pub mod schema;
 
-use std::net::SocketAddr;
 use std::sync::Arc;
 
 use chrono::{DateTime, Utc};
@@ -28,7 +32,7 @@ use rocket::{response::content::Html, Route, State};
 use super::{
     context::Context,
     orm::PooledConnection as Db,
-    request::{Home, Locale, Token},
+    request::{ClientIp, Home, Locale, RequestId, Token},
 };
 
 pub const UID: &'static str = "uid";
@@ -48,8 +52,9 @@ fn handler(
     db: Db,
     home: Home,
     locale: Locale,
-    remote: SocketAddr,
+    client_ip: ClientIp,
     token: Token,
+    request_id: RequestId,
     request: juniper_rocket::GraphQLRequest,
     schema: State<schema::Schema>,
     ctx: State<Arc<Context>>,
@@ -57,6 +62,8 @@ fn handler(
     let Locale(locale) = locale;
     let Home(home) = home;
     let Token(token) = token;
+    let RequestId(request_id) = request_id;
+    let ClientIp(client_ip) = client_ip;
 
     request.execute(
         &schema,
@@ -69,7 +76,8 @@ fn handler(
                 Some(t) => Some(t),
                 None => None,
             },
-            client_ip: format!("{}", remote.ip()),
+            client_ip: client_ip,
+            request_id: request_id,
         },
     )
 }
@@ -198,6 +198,16 @@ graphql_object!(
                 baidu: baidu,
             })
         }
+        field updateSiteRobots(&executor, rules: Vec<nut::graphql::site::mutation::UpdateRobotsRule>) -> FieldResult<H> {
+            gq!(executor, nut::graphql::site::mutation::UpdateRobots{
+                rules: rules,
+            })
+        }
+        field updateSiteTheme(&executor, id: String) -> FieldResult<H> {
+            gq!(executor, nut::graphql::site::mutation::UpdateTheme{
+                id: id,
+            })
+        }
         field updateSiteAuthor(&executor, name: String, email: String) -> FieldResult<H> {
             gq!(executor, nut::graphql::site::mutation::UpdateAuthor{
                 name: name,
@@ -226,6 +236,12 @@ graphql_object!(
             })
         }
 
+        field removeAttachment(&executor, id: String) -> FieldResult<H> {
+            gq!(executor, nut::graphql::attachments::Remove{
+                id: id,
+            })
+        }
+
         field removeLeaveWord(&executor, id: String) -> FieldResult<H> {
             gq!(executor, nut::graphql::leave_words::Remove{
                 id: id,
@@ -64,6 +64,10 @@ graphql_object!(Query: Context |&self| {
         ge!(nut::graphql::members::list(executor.context()))
     }
 
+    field listAttachment(&executor) -> FieldResult<Vec<nut::graphql::attachments::Attachment>> {
+        ge!(nut::graphql::attachments::list(executor.context()))
+    }
+
     field listLeaveWord(&executor) -> FieldResult<Vec<nut::graphql::leave_words::LeaveWord>> {
         ge!(nut::graphql::leave_words::list(executor.context()))
     }
@@ -74,6 +78,12 @@ graphql_object!(Query: Context |&self| {
     field getSiteSeo(&executor) -> FieldResult<nut::graphql::site::models::Seo> {
         ge!(nut::graphql::site::query::seo(executor.context()))
     }
+    field getSiteRobots(&executor) -> FieldResult<Vec<nut::graphql::site::models::RobotsRule>> {
+        ge!(nut::graphql::site::query::robots(executor.context()))
+    }
+    field listSiteTheme(&executor) -> FieldResult<Vec<nut::graphql::site::models::Theme>> {
+        ge!(nut::graphql::site::query::themes(executor.context()))
+    }
     field getSiteAuthor(&executor) -> FieldResult<nut::graphql::site::models::Author> {
         ge!(nut::graphql::site::query::author(executor.context()))
     }
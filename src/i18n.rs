@@ -7,7 +7,7 @@ use serde::ser::Serialize;
 
 use super::{
     errors::{Error, Result},
-    orm::{schema::locales, Connection as Db},
+    orm::{last_insert_id, schema::locales, Connection as Db},
 };
 
 pub fn languages(db: &Db) -> Result<Vec<String>> {
@@ -44,7 +44,7 @@ pub fn set(db: &Db, lang: &String, code: &String, message: &String) -> Result<i6
             Ok(id)
         }
         Err(_) => {
-            let id = insert_into(locales::dsl::locales)
+            insert_into(locales::dsl::locales)
                 .values((
                     locales::dsl::lang.eq(lang),
                     locales::dsl::code.eq(code),
@@ -52,8 +52,8 @@ pub fn set(db: &Db, lang: &String, code: &String, message: &String) -> Result<i6
                     locales::dsl::created_at.eq(&now),
                     locales::dsl::updated_at.eq(&now),
                 ))
-                .returning(locales::dsl::id)
-                .get_result::<i64>(db)?;
+                .execute(db)?;
+            let id = last_insert_id(db)?;
             Ok(id)
         }
     }
@@ -24,6 +24,7 @@ extern crate base64;
 extern crate byteorder;
 extern crate chrono;
 extern crate clap;
+extern crate cron;
 extern crate csv;
 extern crate epub;
 extern crate eui48;
@@ -43,6 +44,7 @@ extern crate log4rs;
 extern crate maxminddb;
 extern crate md5;
 extern crate mime;
+extern crate multipart;
 extern crate mustache;
 extern crate r2d2;
 extern crate r2d2_redis;
@@ -64,6 +66,8 @@ extern crate sha2;
 extern crate sitemap;
 extern crate sodiumoxide;
 extern crate stardict;
+extern crate tar;
+extern crate tera;
 extern crate toml;
 extern crate url;
 extern crate uuid;
@@ -72,24 +76,32 @@ extern crate validator;
 #[macro_use]
 pub mod macros;
 
+pub mod access_log;
 pub mod app;
 pub mod cache;
+pub mod compress;
 pub mod context;
 pub mod env;
 pub mod errors;
 pub mod graphql;
 pub mod i18n;
 pub mod jwt;
+pub mod metrics;
 pub mod oauth;
 pub mod orm;
 pub mod plugins;
 pub mod queue;
+pub mod rate_limit;
 pub mod request;
+pub mod response;
 pub mod rfc;
 pub mod router;
+pub mod security;
+pub mod seo;
 pub mod settings;
 pub mod storage;
 pub mod sys;
+pub mod theme;
 pub mod utils;
 
 use chrono::NaiveDateTime;
@@ -97,3 +109,5 @@ use sitemap::structs::ChangeFreq;
 
 pub type SitemapItem = (String, f32, ChangeFreq, NaiveDateTime);
 pub type RssItem = (String, String, String, NaiveDateTime);
+// user agent, allow, disallow
+pub type RobotsItem = (String, Vec<String>, Vec<String>);
@@ -1,10 +1,10 @@
 #[macro_export]
 macro_rules! l {
-    ($d:expr, $u:expr, $i:expr, $l:expr, $c:expr) => {
-        l!($d, $u, $i, $l, $c, &None::<String>)
+    ($d:expr, $u:expr, $i:expr, $r:expr, $l:expr, $c:expr) => {
+        l!($d, $u, $i, $r, $l, $c, &None::<String>)
     };
-    ($d:expr, $u:expr, $i:expr, $l:expr, $c:expr, $a:expr) => {
-        ::plugins::nut::dao::log::add($d, $u, $i, &t!($d, $l, &$c.to_string(), $a))
+    ($d:expr, $u:expr, $i:expr, $r:expr, $l:expr, $c:expr, $a:expr) => {
+        ::plugins::nut::dao::log::add($d, $u, $i, $r, &t!($d, $l, &$c.to_string(), $a))
     };
 }
 
@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::Cursor;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rocket::{
+    fairing::{self, Info, Kind},
+    http::{Header, Method},
+    Data, Request, Response,
+};
+use serde_json::{self, Value};
+
+// https://prometheus.io/docs/instrumenting/exposition_formats/
+
+pub const HTTP_REQUESTS: &'static str = "arche_http_requests_total";
+pub const HTTP_DURATION: &'static str = "arche_http_request_duration_seconds";
+pub const GRAPHQL_OPERATIONS: &'static str = "arche_graphql_operations_total";
+pub const GRAPHQL_ERRORS: &'static str = "arche_graphql_errors_total";
+pub const CACHE_HITS: &'static str = "arche_cache_hits_total";
+pub const CACHE_MISSES: &'static str = "arche_cache_misses_total";
+pub const CACHE_ERRORS: &'static str = "arche_cache_errors_total";
+pub const QUEUE_PUBLISHED: &'static str = "arche_queue_published_total";
+pub const QUEUE_CONSUMED: &'static str = "arche_queue_consumed_total";
+pub const DB_CONNECTIONS: &'static str = "arche_db_pool_connections";
+pub const DB_IDLE_CONNECTIONS: &'static str = "arche_db_pool_idle_connections";
+pub const DB_MAX_CONNECTIONS: &'static str = "arche_db_pool_max_connections";
+
+const HELP: &'static [(&'static str, &'static str)] = &[
+    (HTTP_REQUESTS, "Total HTTP requests by route and status."),
+    (HTTP_DURATION, "HTTP request latencies in seconds by route."),
+    (GRAPHQL_OPERATIONS, "Total executed GraphQL fields."),
+    (GRAPHQL_ERRORS, "Total failed GraphQL fields."),
+    (CACHE_HITS, "Total cache hits."),
+    (CACHE_MISSES, "Total cache misses."),
+    (CACHE_ERRORS, "Total failed cache commands by command."),
+    (QUEUE_PUBLISHED, "Total published tasks by type."),
+    (QUEUE_CONSUMED, "Total consumed tasks by type and result."),
+    (DB_CONNECTIONS, "Current connections of the database pool."),
+    (DB_IDLE_CONNECTIONS, "Current idle connections of the database pool."),
+    (DB_MAX_CONNECTIONS, "Max connections of the database pool."),
+];
+
+const BUCKETS: &'static [f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+// the fairing keeps the start time in this header
+const STARTED_AT: &'static str = "X-Arche-Started-At";
+
+lazy_static! {
+    pub static ref METRICS: Metrics = Metrics::new();
+}
+
+struct Histogram {
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+pub struct Metrics {
+    counters: Mutex<BTreeMap<(&'static str, String), u64>>,
+    histograms: Mutex<BTreeMap<(&'static str, String), Histogram>>,
+}
+
+fn labels(items: &[(&str, &str)]) -> String {
+    let items: Vec<String> = items
+        .iter()
+        .map(|(k, v)| {
+            format!(
+                "{}=\"{}\"",
+                k,
+                v.replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n")
+            )
+        })
+        .collect();
+    if items.is_empty() {
+        return "".to_string();
+    }
+    format!("{{{}}}", items.join(","))
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            counters: Mutex::new(BTreeMap::new()),
+            histograms: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn inc(&self, name: &'static str, items: &[(&str, &str)]) {
+        if let Ok(mut counters) = self.counters.lock() {
+            *counters.entry((name, labels(items))).or_insert(0) += 1;
+        }
+    }
+
+    pub fn observe(&self, name: &'static str, items: &[(&str, &str)], seconds: f64) {
+        if let Ok(mut histograms) = self.histograms.lock() {
+            let it = histograms
+                .entry((name, labels(items)))
+                .or_insert_with(|| Histogram {
+                    buckets: vec![0; BUCKETS.len()],
+                    sum: 0.0,
+                    count: 0,
+                });
+            for (i, le) in BUCKETS.iter().enumerate() {
+                if seconds <= *le {
+                    it.buckets[i] += 1;
+                }
+            }
+            it.sum += seconds;
+            it.count += 1;
+        }
+    }
+
+    // gauges are sampled by the caller at scrape time
+    pub fn render(&self, gauges: &[(&'static str, f64)]) -> String {
+        let mut buf = String::new();
+        let mut last = "";
+        if let Ok(counters) = self.counters.lock() {
+            for ((name, labels), v) in counters.iter() {
+                if *name != last {
+                    help(&mut buf, name, "counter");
+                    last = *name;
+                }
+                let _ = writeln!(buf, "{}{} {}", name, labels, v);
+            }
+        }
+        if let Ok(histograms) = self.histograms.lock() {
+            for ((name, labels), it) in histograms.iter() {
+                if *name != last {
+                    help(&mut buf, name, "histogram");
+                    last = *name;
+                }
+                // le is appended to the other labels
+                let prefix = if labels.is_empty() {
+                    "{".to_string()
+                } else {
+                    format!("{},", &labels[..labels.len() - 1])
+                };
+                for (i, le) in BUCKETS.iter().enumerate() {
+                    let _ = writeln!(
+                        buf,
+                        "{}_bucket{}le=\"{}\"}} {}",
+                        name, prefix, le, it.buckets[i]
+                    );
+                }
+                let _ = writeln!(
+                    buf,
+                    "{}_bucket{}le=\"+Inf\"}} {}",
+                    name, prefix, it.count
+                );
+                let _ = writeln!(buf, "{}_sum{} {}", name, labels, it.sum);
+                let _ = writeln!(buf, "{}_count{} {}", name, labels, it.count);
+            }
+        }
+        for (name, v) in gauges {
+            help(&mut buf, name, "gauge");
+            let _ = writeln!(buf, "{} {}", name, v);
+        }
+        buf
+    }
+}
+
+fn help(buf: &mut String, name: &str, type_: &str) {
+    if let Some((_, v)) = HELP.iter().find(|(k, _)| *k == name) {
+        let _ = writeln!(buf, "# HELP {} {}", name, v);
+    }
+    let _ = writeln!(buf, "# TYPE {} {}", name, type_);
+}
+
+fn now() -> f64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as f64 + d.subsec_nanos() as f64 / 1e9,
+        Err(_) => 0.0,
+    }
+}
+
+// seconds since the request was received, set by the fairing
+pub fn elapsed(request: &Request) -> Option<f64> {
+    request
+        .headers()
+        .get_one(STARTED_AT)
+        .and_then(|v| v.parse::<f64>().ok())
+        .map(|v| now() - v)
+}
+
+// count fields and errors from a graphql response body
+fn graphql(body: &str) {
+    let body: Value = match serde_json::from_str(body) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    if let Some(data) = body.get("data").and_then(|v| v.as_object()) {
+        for k in data.keys() {
+            METRICS.inc(GRAPHQL_OPERATIONS, &[("field", k.as_str())]);
+        }
+    }
+    if let Some(errors) = body.get("errors").and_then(|v| v.as_array()) {
+        for it in errors {
+            let field = it
+                .get("path")
+                .and_then(|v| v.get(0))
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            METRICS.inc(GRAPHQL_ERRORS, &[("field", field)]);
+        }
+    }
+}
+
+pub struct Fairing;
+
+impl fairing::Fairing for Fairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Prometheus metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    fn on_request(&self, request: &mut Request, _: &Data) {
+        request.replace_header(Header::new(STARTED_AT, now().to_string()));
+    }
+
+    fn on_response(&self, request: &Request, response: &mut Response) {
+        // the template, e.g. /forum/topics/<id>, so each route is one series
+        let route = match request.route() {
+            Some(v) => v.uri.as_str().to_string(),
+            None => "unmatched".to_string(),
+        };
+        let method = request.method().to_string();
+        let status = response.status().code.to_string();
+        METRICS.inc(
+            HTTP_REQUESTS,
+            &[("method", &method), ("route", &route), ("status", &status)],
+        );
+        if let Some(v) = elapsed(request) {
+            METRICS.observe(
+                HTTP_DURATION,
+                &[("method", &method), ("route", &route)],
+                v,
+            );
+        }
+
+        if request.method() == Method::Post && route == "/graphql" {
+            if let Some(body) = response.body_string() {
+                graphql(&body);
+                response.set_sized_body(Cursor::new(body));
+            }
+        }
+    }
+}
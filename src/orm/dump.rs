@@ -0,0 +1,684 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use csv;
+use diesel::{insert_into, prelude::*};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use log;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json;
+use tar;
+
+use super::super::errors::{Error, Result};
+use super::{schema::*, Connection as Db, DRIVER};
+
+// bump it when the layout of the archive changes
+const FORMAT: u32 = 1;
+const MANIFEST: &'static str = "manifest.json";
+// rows of each insert statement
+const BATCH: usize = 256;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Format {
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "csv")]
+    Csv,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("unsupported format {}", s).into()),
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match *self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    pub format: u32,
+    // latest schema migration of the source database
+    pub version: String,
+    pub driver: String,
+    #[serde(rename = "type")]
+    pub type_: Format,
+    pub tables: BTreeMap<String, usize>,
+    pub created_at: NaiveDateTime,
+}
+
+// binary columns are kept as base64 strings, so that both json and csv could hold them
+mod bytes {
+    use base64;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &Vec<u8>, s: S) -> ::std::result::Result<S::Ok, S::Error> {
+        s.serialize_str(&base64::encode(v))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> ::std::result::Result<Vec<u8>, D::Error> {
+        let v = String::deserialize(d)?;
+        base64::decode(&v).map_err(::serde::de::Error::custom)
+    }
+}
+
+// json keeps NULL as null, these are the plain serde ones
+mod nullable {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        v: &Option<String>,
+        s: S,
+    ) -> ::std::result::Result<S::Ok, S::Error> {
+        v.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> ::std::result::Result<Option<String>, D::Error> {
+        Option::<String>::deserialize(d)
+    }
+}
+
+mod option_bytes {
+    use base64;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        v: &Option<Vec<u8>>,
+        s: S,
+    ) -> ::std::result::Result<S::Ok, S::Error> {
+        match *v {
+            Some(ref v) => s.serialize_some(&base64::encode(v)),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> ::std::result::Result<Option<Vec<u8>>, D::Error> {
+        match Option::<String>::deserialize(d)? {
+            Some(ref v) => base64::decode(v)
+                .map(Some)
+                .map_err(::serde::de::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
+// csv has no NULL, an empty cell is an empty string, so NULL is written as \N there
+// and a value starting with a backslash gets one more
+mod csv_nullable {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub const NULL: &'static str = "\\N";
+
+    pub fn encode(v: &Option<String>) -> String {
+        match *v {
+            Some(ref v) if v.starts_with('\\') => format!("\\{}", v),
+            Some(ref v) => v.clone(),
+            None => NULL.to_string(),
+        }
+    }
+
+    pub fn decode(v: String) -> Option<String> {
+        if v == NULL {
+            return None;
+        }
+        if v.starts_with('\\') {
+            return Some(v[1..].to_string());
+        }
+        Some(v)
+    }
+
+    pub fn serialize<S: Serializer>(
+        v: &Option<String>,
+        s: S,
+    ) -> ::std::result::Result<S::Ok, S::Error> {
+        s.serialize_str(&encode(v))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> ::std::result::Result<Option<String>, D::Error> {
+        Ok(decode(String::deserialize(d)?))
+    }
+}
+
+mod csv_option_bytes {
+    use base64;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::csv_nullable::{decode, encode};
+
+    pub fn serialize<S: Serializer>(
+        v: &Option<Vec<u8>>,
+        s: S,
+    ) -> ::std::result::Result<S::Ok, S::Error> {
+        s.serialize_str(&encode(&v.as_ref().map(base64::encode)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> ::std::result::Result<Option<Vec<u8>>, D::Error> {
+        match decode(String::deserialize(d)?) {
+            Some(ref v) => base64::decode(v)
+                .map(Some)
+                .map_err(::serde::de::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
+// every application table, schema_migrations and ar_internal_metadata are not data
+macro_rules! tables {
+    ($($table:ident => $model:ident, $name:tt { $($(#[$meta:meta])* $field:ident: $ty:ty,)+ })+) => {
+        $(
+            #[derive(Queryable, Insertable, Serialize, Deserialize)]
+            #[table_name = $name]
+            struct $model {
+                $($(#[$meta])* $field: $ty,)+
+            }
+        )+
+
+        // the same rows for csv files, only NULLs are written another way
+        mod csv_rows {
+            use chrono::{NaiveDate, NaiveDateTime};
+
+            use super::{bytes, csv_nullable as nullable, csv_option_bytes as option_bytes};
+
+            $(
+                #[derive(Serialize, Deserialize)]
+                pub struct $model {
+                    $($(#[$meta])* $field: $ty,)+
+                }
+
+                impl From<super::$model> for $model {
+                    fn from(it: super::$model) -> Self {
+                        Self { $($field: it.$field,)+ }
+                    }
+                }
+
+                impl From<$model> for super::$model {
+                    fn from(it: $model) -> Self {
+                        Self { $($field: it.$field,)+ }
+                    }
+                }
+            )+
+        }
+
+        pub const TABLES: &'static [&'static str] = &[$($name),+];
+
+        fn export<W: Write>(
+            db: &Db,
+            tar: &mut tar::Builder<W>,
+            fmt: &Format,
+        ) -> Result<BTreeMap<String, usize>> {
+            let mut tables = BTreeMap::new();
+            $(
+                let items = $table::dsl::$table.load::<$model>(db)?;
+                let len = items.len();
+                let buf = match *fmt {
+                    Format::Json => serde_json::to_vec(&items)?,
+                    Format::Csv => to_csv(
+                        items.into_iter().map(csv_rows::$model::from).collect(),
+                    )?,
+                };
+                append(tar, &format!("{}.{}", $name, fmt.extension()), &buf)?;
+                log::info!("dump {} rows from {}", len, $name);
+                tables.insert($name.to_string(), len);
+            )+
+            Ok(tables)
+        }
+
+        fn import(db: &Db, files: &BTreeMap<String, Vec<u8>>, manifest: &Manifest) -> Result<()> {
+            $(
+                if $table::dsl::$table.count().get_result::<i64>(db)? > 0 {
+                    return Err(format!("table {} is not empty", $name).into());
+                }
+                let file = format!("{}.{}", $name, manifest.type_.extension());
+                let items: Vec<$model> = match files.get(&file) {
+                    Some(buf) => match manifest.type_ {
+                        Format::Json => serde_json::from_slice(buf)?,
+                        Format::Csv => from_csv::<csv_rows::$model>(buf)?
+                            .into_iter()
+                            .map($model::from)
+                            .collect(),
+                    },
+                    None => return Err(format!("can't find {}", file).into()),
+                };
+                if Some(&items.len()) != manifest.tables.get($name) {
+                    return Err(format!("bad rows count of {}", $name).into());
+                }
+                for chunk in items.chunks(BATCH) {
+                    insert_into($table::dsl::$table).values(chunk).execute(db)?;
+                }
+                log::info!("restore {} rows into {}", items.len(), $name);
+            )+
+            Ok(())
+        }
+    };
+}
+
+tables! {
+    attachments => Attachment, "attachments" {
+        id: i64,
+        user_id: i64,
+        name: String,
+        size: String,
+        mime_type: String,
+        url: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    cards => Card, "cards" {
+        id: i64,
+        title: String,
+        body: String,
+        media_type: String,
+        action: String,
+        href: String,
+        logo: String,
+        loc: String,
+        lang: String,
+        position: i16,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    caring_posts => CaringPost, "caring_posts" {
+        id: i64,
+        topic_id: i64,
+        user_id: i64,
+        method: String,
+        body: String,
+        media_type: String,
+        begin: NaiveDateTime,
+        end: NaiveDateTime,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    caring_topics => CaringTopic, "caring_topics" {
+        id: i64,
+        user_id: i64,
+        member_id: i64,
+        tag: String,
+        name: String,
+        gender: String,
+        age: i16,
+        #[serde(with = "nullable")]
+        phone: Option<String>,
+        #[serde(with = "nullable")]
+        email: Option<String>,
+        #[serde(with = "nullable")]
+        address: Option<String>,
+        reason: String,
+        media_type: String,
+        status: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    forum_posts => ForumPost, "forum_posts" {
+        id: i64,
+        user_id: i64,
+        topic_id: i64,
+        post_id: Option<i64>,
+        body: String,
+        media_type: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    forum_tags => ForumTag, "forum_tags" {
+        id: i64,
+        name: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    forum_topics => ForumTopic, "forum_topics" {
+        id: i64,
+        user_id: i64,
+        lang: String,
+        title: String,
+        body: String,
+        media_type: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    forum_topics_tags => ForumTopicTag, "forum_topics_tags" {
+        id: i64,
+        topic_id: i64,
+        tag_id: i64,
+    }
+    friend_links => FriendLink, "friend_links" {
+        id: i64,
+        title: String,
+        home: String,
+        logo: String,
+        position: i16,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    jobs => Job, "jobs" {
+        id: i64,
+        type_: String,
+        content_type: String,
+        priority: i16,
+        #[serde(with = "bytes")]
+        payload: Vec<u8>,
+        attempts: i16,
+        run_at: NaiveDateTime,
+        #[serde(with = "nullable")]
+        last_error: Option<String>,
+        failed_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    leave_words => LeaveWord, "leave_words" {
+        id: i64,
+        body: String,
+        media_type: String,
+        created_at: NaiveDateTime,
+    }
+    links => Link, "links" {
+        id: i64,
+        href: String,
+        label: String,
+        loc: String,
+        lang: String,
+        x: i16,
+        y: i16,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    locales => Locale, "locales" {
+        id: i64,
+        lang: String,
+        code: String,
+        message: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    logs => Log, "logs" {
+        id: i64,
+        user_id: i64,
+        ip: String,
+        message: String,
+        created_at: NaiveDateTime,
+        #[serde(with = "nullable")]
+        request_id: Option<String>,
+    }
+    members => Member, "members" {
+        id: i64,
+        nick_name: String,
+        real_name: String,
+        gender: String,
+        birthday: NaiveDate,
+        #[serde(with = "nullable")]
+        phone: Option<String>,
+        #[serde(with = "nullable")]
+        email: Option<String>,
+        #[serde(with = "nullable")]
+        address: Option<String>,
+        #[serde(with = "nullable")]
+        line: Option<String>,
+        #[serde(with = "nullable")]
+        wechat: Option<String>,
+        #[serde(with = "nullable")]
+        skype: Option<String>,
+        #[serde(with = "nullable")]
+        weibo: Option<String>,
+        #[serde(with = "nullable")]
+        facebook: Option<String>,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    notifications => Notification, "notifications" {
+        id: i64,
+        user_id: i64,
+        url: String,
+        body: String,
+        media_type: String,
+        level: String,
+        read: bool,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    policies => Policy, "policies" {
+        id: i64,
+        user_id: i64,
+        role: String,
+        #[serde(with = "nullable")]
+        resource: Option<String>,
+        nbf: NaiveDate,
+        exp: NaiveDate,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    settings => Setting, "settings" {
+        id: i64,
+        key: String,
+        #[serde(with = "bytes")]
+        value: Vec<u8>,
+        #[serde(with = "option_bytes")]
+        salt: Option<Vec<u8>>,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    survey_fields => SurveyField, "survey_fields" {
+        id: i64,
+        form_id: i64,
+        name: String,
+        label: String,
+        #[serde(with = "nullable")]
+        options: Option<String>,
+        type_: String,
+        required: bool,
+        sort: i16,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    survey_forms => SurveyForm, "survey_forms" {
+        id: i64,
+        user_id: i64,
+        title: String,
+        body: String,
+        media_type: String,
+        uid: String,
+        mode: String,
+        nbf: NaiveDate,
+        exp: NaiveDate,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    survey_records => SurveyRecord, "survey_records" {
+        id: i64,
+        field_id: i64,
+        order: String,
+        #[serde(with = "nullable")]
+        value: Option<String>,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    survey_subscribers => SurveySubscriber, "survey_subscribers" {
+        id: i64,
+        form_id: i64,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    users => User, "users" {
+        id: i64,
+        name: String,
+        email: String,
+        #[serde(with = "option_bytes")]
+        password: Option<Vec<u8>>,
+        uid: String,
+        provider_type: String,
+        provider_id: String,
+        logo: String,
+        sign_in_count: i64,
+        current_sign_in_at: Option<NaiveDateTime>,
+        #[serde(with = "nullable")]
+        current_sign_in_ip: Option<String>,
+        last_sign_in_at: Option<NaiveDateTime>,
+        #[serde(with = "nullable")]
+        last_sign_in_ip: Option<String>,
+        confirmed_at: Option<NaiveDateTime>,
+        locked_at: Option<NaiveDateTime>,
+        deleted_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+    votes => Vote, "votes" {
+        id: i64,
+        point: i64,
+        resource_type: String,
+        resource_id: i64,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+}
+
+// sequences are not moved by explicit ids on postgresql
+#[cfg(feature = "postgresql")]
+fn reset_sequences(db: &Db) -> Result<()> {
+    use diesel::connection::SimpleConnection;
+    for it in TABLES {
+        db.batch_execute(&format!(
+            "SELECT setval(pg_get_serial_sequence('{table}', 'id'), \
+             COALESCE((SELECT MAX(id) FROM {table}), 0) + 1, false)",
+            table = it
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "postgresql"))]
+fn reset_sequences(_db: &Db) -> Result<()> {
+    Ok(())
+}
+
+fn version(db: &Db) -> Result<String> {
+    let it = schema_migrations::dsl::schema_migrations
+        .select(schema_migrations::dsl::version)
+        .order(schema_migrations::dsl::version.desc())
+        .first::<String>(db)
+        .optional()?;
+    match it {
+        Some(v) => Ok(v),
+        None => Err("database is not migrated".into()),
+    }
+}
+
+fn to_csv<T: Serialize>(items: Vec<T>) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    {
+        let mut wrt = csv::Writer::from_writer(&mut buf);
+        for it in items {
+            wrt.serialize(it)?;
+        }
+        wrt.flush()?;
+    }
+    Ok(buf)
+}
+
+fn from_csv<T: DeserializeOwned>(buf: &[u8]) -> Result<Vec<T>> {
+    let mut items = Vec::new();
+    for it in csv::Reader::from_reader(buf).deserialize() {
+        items.push(it?);
+    }
+    Ok(items)
+}
+
+// one snapshot for every table, so that the rows of a live install match each other
+#[cfg(feature = "postgresql")]
+fn snapshot<T, F: FnOnce() -> Result<T>>(db: &Db, f: F) -> Result<T> {
+    db.build_transaction().repeatable_read().read_only().run(f)
+}
+
+#[cfg(feature = "mysql")]
+fn snapshot<T, F: FnOnce() -> Result<T>>(db: &Db, f: F) -> Result<T> {
+    use diesel::connection::SimpleConnection;
+    // applies to the next transaction only
+    db.batch_execute("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")?;
+    db.transaction::<_, Error, _>(f)
+}
+
+#[cfg(feature = "sqlite")]
+fn snapshot<T, F: FnOnce() -> Result<T>>(db: &Db, f: F) -> Result<T> {
+    db.transaction::<_, Error, _>(f)
+}
+
+fn append<W: Write>(tar: &mut tar::Builder<W>, name: &String, buf: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(buf.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp() as u64);
+    header.set_cksum();
+    tar.append_data(&mut header, name, buf)?;
+    Ok(())
+}
+
+// write a tar.gz archive with a manifest and one file per table
+pub fn dump<W: Write>(db: &Db, out: W, fmt: &Format) -> Result<Manifest> {
+    let mut tar = tar::Builder::new(GzEncoder::new(out, Compression::default()));
+    let (version, tables) = snapshot(db, || Ok((version(db)?, export(db, &mut tar, fmt)?)))?;
+    let manifest = Manifest {
+        format: FORMAT,
+        version: version,
+        driver: DRIVER.to_string(),
+        type_: fmt.clone(),
+        tables: tables,
+        created_at: Utc::now().naive_utc(),
+    };
+    append(
+        &mut tar,
+        &MANIFEST.to_string(),
+        &serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    tar.into_inner()?.finish()?;
+    Ok(manifest)
+}
+
+// load an archive into an empty database migrated to the same version, must be called in a transaction
+pub fn restore<R: Read>(db: &Db, input: R) -> Result<Manifest> {
+    let mut files = BTreeMap::new();
+    let mut tar = tar::Archive::new(GzDecoder::new(input));
+    for it in tar.entries()? {
+        let mut it = it?;
+        let name = match it.path()?.to_str() {
+            Some(v) => v.to_string(),
+            None => continue,
+        };
+        let mut buf = Vec::new();
+        it.read_to_end(&mut buf)?;
+        files.insert(name, buf);
+    }
+
+    let manifest: Manifest = match files.get(MANIFEST) {
+        Some(buf) => serde_json::from_slice(buf)?,
+        None => return Err(format!("can't find {}", MANIFEST).into()),
+    };
+    if manifest.format != FORMAT {
+        return Err(format!("unsupported archive format {}", manifest.format).into());
+    }
+    let version = version(db)?;
+    if manifest.version != version {
+        return Err(format!(
+            "archive is at version {}, but database is at {}",
+            manifest.version, version
+        ).into());
+    }
+
+    import(db, &files, &manifest)?;
+    reset_sequences(db)?;
+    Ok(manifest)
+}
@@ -0,0 +1,105 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::{connection::SimpleConnection, delete, insert_into, prelude::*, Connection};
+use log;
+
+use super::super::errors::{Error, Result};
+use super::{
+    migrations::{MIGRATIONS, SCHEMA_MIGRATIONS},
+    schema::schema_migrations,
+    Connection as Db,
+};
+
+// same versions as the activerecord migrations under tools/db/migrate
+pub struct Migration {
+    pub version: &'static str,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+impl Migration {
+    fn run(&self, db: &Db, script: &str) -> Result<()> {
+        // mysql rejects an empty statement
+        if !script.trim().is_empty() {
+            db.batch_execute(script)?;
+        }
+        Ok(())
+    }
+}
+
+fn applied(db: &Db) -> Result<Vec<(String, NaiveDateTime)>> {
+    db.batch_execute(SCHEMA_MIGRATIONS)?;
+    let items = schema_migrations::dsl::schema_migrations
+        .select((
+            schema_migrations::dsl::version,
+            schema_migrations::dsl::created_at,
+        ))
+        .order(schema_migrations::dsl::version.asc())
+        .load::<(String, NaiveDateTime)>(db)?;
+    Ok(items)
+}
+
+pub fn migrate(db: &Db) -> Result<()> {
+    let versions: Vec<String> = applied(db)?.into_iter().map(|(v, _)| v).collect();
+    for it in MIGRATIONS {
+        if versions.iter().any(|v| v == it.version) {
+            continue;
+        }
+        log::info!("migrate {}_{}", it.version, it.name);
+        db.transaction::<_, Error, _>(|| {
+            it.run(db, it.up)?;
+            let now = Utc::now().naive_utc();
+            insert_into(schema_migrations::dsl::schema_migrations)
+                .values((
+                    schema_migrations::dsl::version.eq(it.version),
+                    schema_migrations::dsl::created_at.eq(&now),
+                ))
+                .execute(db)?;
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+// revert the latest applied migration, return false if nothing to do
+pub fn rollback(db: &Db) -> Result<bool> {
+    let version = match applied(db)?.pop() {
+        Some((v, _)) => v,
+        None => return Ok(false),
+    };
+    let it = match MIGRATIONS.iter().find(|it| it.version == version) {
+        Some(v) => v,
+        None => return Err(format!("unknown migration {}", version).into()),
+    };
+    log::info!("rollback {}_{}", it.version, it.name);
+    db.transaction::<_, Error, _>(|| {
+        it.run(db, it.down)?;
+        delete(
+            schema_migrations::dsl::schema_migrations
+                .filter(schema_migrations::dsl::version.eq(it.version)),
+        ).execute(db)?;
+        Ok(())
+    })?;
+    Ok(true)
+}
+
+pub fn status(db: &Db) -> Result<Vec<(&'static Migration, Option<NaiveDateTime>)>> {
+    let versions = applied(db)?;
+    Ok(MIGRATIONS
+        .iter()
+        .map(|it| {
+            (
+                it,
+                versions
+                    .iter()
+                    .find(|(v, _)| v == it.version)
+                    .map(|(_, r)| *r),
+            )
+        })
+        .collect())
+}
+
+pub fn reset(db: &Db) -> Result<()> {
+    while rollback(db)? {}
+    migrate(db)
+}
@@ -1,16 +1,24 @@
 #[cfg(feature = "mysql")]
 pub mod mysql;
 #[cfg(feature = "mysql")]
-pub use self::mysql::{schema, Config, Connection, DRIVER};
+pub use self::mysql::{migrations, schema, Config, Connection, DRIVER, LAST_INSERT_ID};
 
 #[cfg(feature = "postgresql")]
 pub mod postgresql;
 #[cfg(feature = "postgresql")]
-pub use self::postgresql::{schema, Config, Connection, DRIVER};
+pub use self::postgresql::{migrations, schema, Config, Connection, DRIVER, LAST_INSERT_ID};
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::{migrations, schema, Config, Connection, DRIVER, LAST_INSERT_ID};
+
+pub mod dump;
+pub mod migration;
 
 use std::ops::Deref;
 
-use diesel::r2d2::ConnectionManager;
+use diesel::{dsl::sql, prelude::*, r2d2::ConnectionManager, select, sql_types::BigInt};
 use r2d2;
 use rocket::{
     http::Status,
@@ -18,6 +26,13 @@ use rocket::{
     Outcome, Request, State,
 };
 
+use self::schema::{forum_posts, forum_topics, users};
+use super::errors::Result;
+
+// print-schema only emits these for foreign keys, which the migrations don't declare
+joinable!(forum_posts -> users (user_id));
+joinable!(forum_topics -> users (user_id));
+
 pub type Pool = r2d2::Pool<ConnectionManager<Connection>>;
 pub struct PooledConnection(pub r2d2::PooledConnection<ConnectionManager<Connection>>);
 
@@ -40,3 +55,8 @@ impl Deref for PooledConnection {
         &self.0
     }
 }
+
+// mysql and sqlite have no "INSERT ... RETURNING", call it after the insert with the same connection
+pub fn last_insert_id(db: &Connection) -> Result<i64> {
+    Ok(select(sql::<BigInt>(LAST_INSERT_ID)).get_result::<i64>(db)?)
+}
@@ -1,3 +1,4 @@
+pub mod migrations;
 pub mod schema;
 
 use diesel::mysql::{r2d2::ConnectionManager, MysqlConnection};
@@ -6,6 +7,8 @@ use r2d2::Pool;
 use super::super::errors::Result;
 
 pub const DRIVER: &'static str = "mysql";
+// id of the latest inserted row in this session
+pub const LAST_INSERT_ID: &'static str = "CAST(LAST_INSERT_ID() AS SIGNED)";
 
 pub type Connection = MysqlConnection;
 
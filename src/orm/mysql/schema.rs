@@ -31,7 +31,7 @@ table! {
         logo -> Varchar,
         loc -> Varchar,
         lang -> Varchar,
-        position -> Tinyint,
+        position -> Smallint,
         created_at -> Datetime,
         updated_at -> Datetime,
     }
@@ -60,7 +60,7 @@ table! {
         tag -> Varchar,
         name -> Varchar,
         gender -> Varchar,
-        age -> Tinyint,
+        age -> Smallint,
         phone -> Nullable<Varchar>,
         email -> Nullable<Varchar>,
         address -> Nullable<Varchar>,
@@ -121,7 +121,24 @@ table! {
         title -> Varchar,
         home -> Varchar,
         logo -> Varchar,
-        position -> Tinyint,
+        position -> Smallint,
+        created_at -> Datetime,
+        updated_at -> Datetime,
+    }
+}
+
+table! {
+    jobs (id) {
+        id -> Bigint,
+        #[sql_name = "type"]
+        type_ -> Varchar,
+        content_type -> Varchar,
+        priority -> Smallint,
+        payload -> Blob,
+        attempts -> Smallint,
+        run_at -> Datetime,
+        last_error -> Nullable<Text>,
+        failed_at -> Nullable<Datetime>,
         created_at -> Datetime,
         updated_at -> Datetime,
     }
@@ -143,8 +160,8 @@ table! {
         label -> Varchar,
         loc -> Varchar,
         lang -> Varchar,
-        x -> Tinyint,
-        y -> Tinyint,
+        x -> Smallint,
+        y -> Smallint,
         created_at -> Datetime,
         updated_at -> Datetime,
     }
@@ -168,6 +185,7 @@ table! {
         ip -> Varchar,
         message -> Varchar,
         created_at -> Datetime,
+        request_id -> Nullable<Varchar>,
     }
 }
 
@@ -246,7 +264,7 @@ table! {
         #[sql_name = "type"]
         type_ -> Varchar,
         required -> Bool,
-        sort -> Tinyint,
+        sort -> Smallint,
         created_at -> Datetime,
         updated_at -> Datetime,
     }
@@ -334,6 +352,7 @@ allow_tables_to_appear_in_same_query!(
     forum_topics,
     forum_topics_tags,
     friend_links,
+    jobs,
     leave_words,
     links,
     locales,
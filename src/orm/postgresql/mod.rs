@@ -1,3 +1,4 @@
+pub mod migrations;
 pub mod schema;
 
 use diesel::{pg::PgConnection, r2d2::ConnectionManager};
@@ -6,6 +7,8 @@ use r2d2::Pool;
 use super::super::errors::Result;
 
 pub const DRIVER: &'static str = "postgresql";
+// id of the latest inserted row in this session
+pub const LAST_INSERT_ID: &'static str = "lastval()";
 
 // logging:
 // edit "/var/lib/postgres/data/postgresql.conf", change "log_statement = 'all'"
@@ -127,6 +127,23 @@ table! {
     }
 }
 
+table! {
+    jobs (id) {
+        id -> Int8,
+        #[sql_name = "type"]
+        type_ -> Varchar,
+        content_type -> Varchar,
+        priority -> Int2,
+        payload -> Bytea,
+        attempts -> Int2,
+        run_at -> Timestamp,
+        last_error -> Nullable<Text>,
+        failed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 table! {
     leave_words (id) {
         id -> Int8,
@@ -168,6 +185,7 @@ table! {
         ip -> Varchar,
         message -> Varchar,
         created_at -> Timestamp,
+        request_id -> Nullable<Varchar>,
     }
 }
 
@@ -334,6 +352,7 @@ allow_tables_to_appear_in_same_query!(
     forum_topics,
     forum_topics_tags,
     friend_links,
+    jobs,
     leave_words,
     links,
     locales,
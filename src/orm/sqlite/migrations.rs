@@ -0,0 +1,568 @@
+use super::super::migration::Migration;
+
+pub const SCHEMA_MIGRATIONS: &'static str = r#"
+CREATE TABLE IF NOT EXISTS "schema_migrations" (
+    "version" VARCHAR(255) NOT NULL PRIMARY KEY,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+"#;
+
+pub const MIGRATIONS: &'static [Migration] = &[
+    // created_at is added when creating schema_migrations
+    Migration {
+        version: "20180625081259",
+        name: "add_created_at_to_schema_migrations",
+        up: "",
+        down: "",
+    },
+    Migration {
+        version: "20180625082406",
+        name: "create_locales",
+        up: r#"
+CREATE TABLE "locales" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "lang" VARCHAR(8) NOT NULL,
+    "code" VARCHAR(255) NOT NULL,
+    "message" TEXT NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_locales_on_lang" ON "locales" ("lang");
+CREATE INDEX "index_locales_on_code" ON "locales" ("code");
+CREATE UNIQUE INDEX "index_locales_on_lang_and_code" ON "locales" ("lang", "code");
+"#,
+        down: r#"
+DROP TABLE "locales";
+"#,
+    },
+    Migration {
+        version: "20180625083744",
+        name: "create_settings",
+        up: r#"
+CREATE TABLE "settings" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "key" VARCHAR(255) NOT NULL,
+    "value" BLOB NOT NULL,
+    "salt" BLOB,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE UNIQUE INDEX "index_settings_on_key" ON "settings" ("key");
+"#,
+        down: r#"
+DROP TABLE "settings";
+"#,
+    },
+    Migration {
+        version: "20180625153603",
+        name: "create_users",
+        up: r#"
+CREATE TABLE "users" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "name" VARCHAR(32) NOT NULL,
+    "email" VARCHAR(255) NOT NULL,
+    "password" BLOB,
+    "uid" VARCHAR(36) NOT NULL,
+    "provider_type" VARCHAR(16) NOT NULL,
+    "provider_id" VARCHAR(255) NOT NULL,
+    "logo" VARCHAR(255) NOT NULL,
+    "sign_in_count" BIGINT NOT NULL,
+    "current_sign_in_at" DATETIME,
+    "current_sign_in_ip" VARCHAR(39),
+    "last_sign_in_at" DATETIME,
+    "last_sign_in_ip" VARCHAR(39),
+    "confirmed_at" DATETIME,
+    "locked_at" DATETIME,
+    "deleted_at" DATETIME,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_users_on_name" ON "users" ("name");
+CREATE INDEX "index_users_on_email" ON "users" ("email");
+CREATE UNIQUE INDEX "index_users_on_provider_type_and_provider_id" ON "users" ("provider_type", "provider_id");
+CREATE UNIQUE INDEX "index_users_on_uid" ON "users" ("uid");
+"#,
+        down: r#"
+DROP TABLE "users";
+"#,
+    },
+    Migration {
+        version: "20180625153631",
+        name: "create_logs",
+        up: r#"
+CREATE TABLE "logs" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "ip" VARCHAR(39) NOT NULL,
+    "message" VARCHAR(255) NOT NULL,
+    "created_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_logs_on_user_id" ON "logs" ("user_id");
+CREATE INDEX "index_logs_on_ip" ON "logs" ("ip");
+"#,
+        down: r#"
+DROP TABLE "logs";
+"#,
+    },
+    Migration {
+        version: "20180625153722",
+        name: "create_policies",
+        up: r#"
+CREATE TABLE "policies" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "role" VARCHAR(255) NOT NULL,
+    "resource" VARCHAR(255),
+    "nbf" DATE NOT NULL,
+    "exp" DATE NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_policies_on_user_id" ON "policies" ("user_id");
+CREATE INDEX "index_policies_on_role" ON "policies" ("role");
+"#,
+        down: r#"
+DROP TABLE "policies";
+"#,
+    },
+    Migration {
+        version: "20180625170701",
+        name: "create_votes",
+        up: r#"
+CREATE TABLE "votes" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "point" BIGINT NOT NULL,
+    "resource_type" VARCHAR(255) NOT NULL,
+    "resource_id" BIGINT NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_votes_on_resource_type" ON "votes" ("resource_type");
+CREATE UNIQUE INDEX "index_votes_on_resource_type_and_resource_id" ON "votes" ("resource_type", "resource_id");
+"#,
+        down: r#"
+DROP TABLE "votes";
+"#,
+    },
+    Migration {
+        version: "20180625170712",
+        name: "create_leave_words",
+        up: r#"
+CREATE TABLE "leave_words" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "body" TEXT NOT NULL,
+    "media_type" VARCHAR(8) NOT NULL,
+    "created_at" DATETIME NOT NULL
+);
+"#,
+        down: r#"
+DROP TABLE "leave_words";
+"#,
+    },
+    Migration {
+        version: "20180625170718",
+        name: "create_links",
+        up: r#"
+CREATE TABLE "links" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "href" VARCHAR(255) NOT NULL,
+    "label" VARCHAR(32) NOT NULL,
+    "loc" VARCHAR(16) NOT NULL,
+    "lang" VARCHAR(8) NOT NULL,
+    "x" TINYINT NOT NULL,
+    "y" TINYINT NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_links_on_lang" ON "links" ("lang");
+CREATE INDEX "index_links_on_lang_and_loc" ON "links" ("lang", "loc");
+"#,
+        down: r#"
+DROP TABLE "links";
+"#,
+    },
+    Migration {
+        version: "20180625170721",
+        name: "create_cards",
+        up: r#"
+CREATE TABLE "cards" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "title" VARCHAR(255) NOT NULL,
+    "body" TEXT NOT NULL,
+    "media_type" VARCHAR(8) NOT NULL,
+    "action" VARCHAR(32) NOT NULL,
+    "href" VARCHAR(255) NOT NULL,
+    "logo" VARCHAR(255) NOT NULL,
+    "loc" VARCHAR(16) NOT NULL,
+    "lang" VARCHAR(8) NOT NULL,
+    "position" TINYINT NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_cards_on_lang" ON "cards" ("lang");
+CREATE INDEX "index_cards_on_lang_and_loc" ON "cards" ("lang", "loc");
+"#,
+        down: r#"
+DROP TABLE "cards";
+"#,
+    },
+    Migration {
+        version: "20180625170728",
+        name: "create_notifications",
+        up: r#"
+CREATE TABLE "notifications" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "url" VARCHAR(255) NOT NULL,
+    "body" VARCHAR(1024) NOT NULL,
+    "media_type" VARCHAR(8) NOT NULL,
+    "level" VARCHAR(1) NOT NULL,
+    "read" BOOLEAN NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_notifications_on_user_id" ON "notifications" ("user_id");
+CREATE INDEX "index_notifications_on_level" ON "notifications" ("level");
+"#,
+        down: r#"
+DROP TABLE "notifications";
+"#,
+    },
+    Migration {
+        version: "20180625170733",
+        name: "create_friend_links",
+        up: r#"
+CREATE TABLE "friend_links" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "title" VARCHAR(32) NOT NULL,
+    "home" VARCHAR(255) NOT NULL,
+    "logo" VARCHAR(255) NOT NULL,
+    "position" TINYINT NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_friend_links_on_title" ON "friend_links" ("title");
+"#,
+        down: r#"
+DROP TABLE "friend_links";
+"#,
+    },
+    Migration {
+        version: "20180625170747",
+        name: "create_attachments",
+        up: r#"
+CREATE TABLE "attachments" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "name" VARCHAR(255) NOT NULL,
+    "size" VARCHAR(8) NOT NULL,
+    "mime_type" VARCHAR(64) NOT NULL,
+    "url" VARCHAR(255) NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_attachments_on_user_id" ON "attachments" ("user_id");
+CREATE INDEX "index_attachments_on_name" ON "attachments" ("name");
+"#,
+        down: r#"
+DROP TABLE "attachments";
+"#,
+    },
+    Migration {
+        version: "20180625170757",
+        name: "create_members",
+        up: r#"
+CREATE TABLE "members" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "nick_name" VARCHAR(255) NOT NULL,
+    "real_name" VARCHAR(255) NOT NULL,
+    "gender" VARCHAR(1) NOT NULL,
+    "birthday" DATE NOT NULL,
+    "phone" VARCHAR(255),
+    "email" VARCHAR(255),
+    "address" VARCHAR(255),
+    "line" VARCHAR(255),
+    "wechat" VARCHAR(255),
+    "skype" VARCHAR(255),
+    "weibo" VARCHAR(255),
+    "facebook" VARCHAR(255),
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE UNIQUE INDEX "index_members_on_nick_name" ON "members" ("nick_name");
+CREATE INDEX "index_members_on_real_name" ON "members" ("real_name");
+"#,
+        down: r#"
+DROP TABLE "members";
+"#,
+    },
+    Migration {
+        version: "20180625173235",
+        name: "create_survey_forms",
+        up: r#"
+CREATE TABLE "survey_forms" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "title" VARCHAR(255) NOT NULL,
+    "body" TEXT NOT NULL,
+    "media_type" VARCHAR(8) NOT NULL,
+    "uid" VARCHAR(36) NOT NULL,
+    "mode" VARCHAR(16) NOT NULL,
+    "nbf" DATE NOT NULL,
+    "exp" DATE NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_survey_forms_on_user_id" ON "survey_forms" ("user_id");
+CREATE INDEX "index_survey_forms_on_title" ON "survey_forms" ("title");
+CREATE UNIQUE INDEX "index_survey_forms_on_uid" ON "survey_forms" ("uid");
+"#,
+        down: r#"
+DROP TABLE "survey_forms";
+"#,
+    },
+    Migration {
+        version: "20180625173309",
+        name: "create_survey_fields",
+        up: r#"
+CREATE TABLE "survey_fields" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "form_id" BIGINT NOT NULL,
+    "name" VARCHAR(32) NOT NULL,
+    "label" VARCHAR(255) NOT NULL,
+    "options" TEXT,
+    "type" VARCHAR(16) NOT NULL,
+    "required" BOOLEAN NOT NULL,
+    "sort" TINYINT NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_survey_fields_on_form_id" ON "survey_fields" ("form_id");
+CREATE INDEX "index_survey_fields_on_label" ON "survey_fields" ("label");
+CREATE UNIQUE INDEX "index_survey_fields_on_form_id_and_name" ON "survey_fields" ("form_id", "name");
+"#,
+        down: r#"
+DROP TABLE "survey_fields";
+"#,
+    },
+    Migration {
+        version: "20180625173313",
+        name: "create_survey_records",
+        up: r#"
+CREATE TABLE "survey_records" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "field_id" BIGINT NOT NULL,
+    "order" VARCHAR(36) NOT NULL,
+    "value" TEXT,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_survey_records_on_field_id" ON "survey_records" ("field_id");
+CREATE INDEX "index_survey_records_on_order" ON "survey_records" ("order");
+CREATE UNIQUE INDEX "index_survey_records_on_field_id_and_order" ON "survey_records" ("field_id", "order");
+"#,
+        down: r#"
+DROP TABLE "survey_records";
+"#,
+    },
+    Migration {
+        version: "20180625173346",
+        name: "create_survey_subscribers",
+        up: r#"
+CREATE TABLE "survey_subscribers" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "form_id" BIGINT NOT NULL,
+    "email" VARCHAR(255) NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_survey_subscribers_on_form_id" ON "survey_subscribers" ("form_id");
+CREATE INDEX "index_survey_subscribers_on_email" ON "survey_subscribers" ("email");
+CREATE UNIQUE INDEX "index_survey_subscribers_on_form_id_and_email" ON "survey_subscribers" ("form_id", "email");
+"#,
+        down: r#"
+DROP TABLE "survey_subscribers";
+"#,
+    },
+    Migration {
+        version: "20180626041415",
+        name: "create_forum_tags",
+        up: r#"
+CREATE TABLE "forum_tags" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "name" VARCHAR(255) NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE UNIQUE INDEX "index_forum_tags_on_name" ON "forum_tags" ("name");
+"#,
+        down: r#"
+DROP TABLE "forum_tags";
+"#,
+    },
+    Migration {
+        version: "20180626041448",
+        name: "create_forum_topics",
+        up: r#"
+CREATE TABLE "forum_topics" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "lang" VARCHAR(8) NOT NULL,
+    "title" VARCHAR(255) NOT NULL,
+    "body" TEXT NOT NULL,
+    "media_type" VARCHAR(8) NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_forum_topics_on_user_id" ON "forum_topics" ("user_id");
+CREATE TABLE "forum_topics_tags" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "topic_id" BIGINT NOT NULL,
+    "tag_id" BIGINT NOT NULL
+);
+CREATE INDEX "index_forum_topics_tags_on_topic_id" ON "forum_topics_tags" ("topic_id");
+CREATE INDEX "index_forum_topics_tags_on_tag_id" ON "forum_topics_tags" ("tag_id");
+CREATE INDEX "index_forum_topics_on_title" ON "forum_topics" ("title");
+CREATE INDEX "index_forum_topics_on_lang" ON "forum_topics" ("lang");
+CREATE UNIQUE INDEX "index_forum_topics_tags_on_topic_id_and_tag_id" ON "forum_topics_tags" ("topic_id", "tag_id");
+"#,
+        down: r#"
+DROP TABLE "forum_topics_tags";
+DROP TABLE "forum_topics";
+"#,
+    },
+    Migration {
+        version: "20180626042813",
+        name: "create_forum_posts",
+        up: r#"
+CREATE TABLE "forum_posts" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "topic_id" BIGINT NOT NULL,
+    "post_id" BIGINT,
+    "body" TEXT NOT NULL,
+    "media_type" VARCHAR(8) NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_forum_posts_on_user_id" ON "forum_posts" ("user_id");
+CREATE INDEX "index_forum_posts_on_topic_id" ON "forum_posts" ("topic_id");
+CREATE INDEX "index_forum_posts_on_post_id" ON "forum_posts" ("post_id");
+"#,
+        down: r#"
+DROP TABLE "forum_posts";
+"#,
+    },
+    Migration {
+        version: "20180714173003",
+        name: "create_caring_topics",
+        up: r#"
+CREATE TABLE "caring_topics" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "member_id" BIGINT NOT NULL,
+    "tag" VARCHAR(32) NOT NULL,
+    "name" VARCHAR(36) NOT NULL,
+    "gender" VARCHAR(1) NOT NULL,
+    "age" TINYINT NOT NULL,
+    "phone" VARCHAR(255),
+    "email" VARCHAR(255),
+    "address" VARCHAR(255),
+    "reason" TEXT NOT NULL,
+    "media_type" VARCHAR(8) NOT NULL,
+    "status" VARCHAR(16) NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_caring_topics_on_user_id" ON "caring_topics" ("user_id");
+CREATE INDEX "index_caring_topics_on_member_id" ON "caring_topics" ("member_id");
+CREATE INDEX "index_caring_topics_on_tag" ON "caring_topics" ("tag");
+CREATE INDEX "index_caring_topics_on_name" ON "caring_topics" ("name");
+CREATE INDEX "index_caring_topics_on_gender" ON "caring_topics" ("gender");
+CREATE INDEX "index_caring_topics_on_status" ON "caring_topics" ("status");
+"#,
+        down: r#"
+DROP TABLE "caring_topics";
+"#,
+    },
+    Migration {
+        version: "20180714173012",
+        name: "create_caring_posts",
+        up: r#"
+CREATE TABLE "caring_posts" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "topic_id" BIGINT NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "method" VARCHAR(255) NOT NULL,
+    "body" TEXT NOT NULL,
+    "media_type" VARCHAR(8) NOT NULL,
+    "begin" DATETIME NOT NULL,
+    "end" DATETIME NOT NULL,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_caring_posts_on_topic_id" ON "caring_posts" ("topic_id");
+CREATE INDEX "index_caring_posts_on_user_id" ON "caring_posts" ("user_id");
+CREATE INDEX "index_caring_posts_on_method" ON "caring_posts" ("method");
+"#,
+        down: r#"
+DROP TABLE "caring_posts";
+"#,
+    },
+    Migration {
+        version: "20180721093012",
+        name: "create_jobs",
+        up: r#"
+CREATE TABLE "jobs" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "type" VARCHAR(32) NOT NULL,
+    "content_type" VARCHAR(32) NOT NULL,
+    "priority" SMALLINT NOT NULL,
+    "payload" BLOB NOT NULL,
+    "attempts" SMALLINT NOT NULL,
+    "run_at" DATETIME NOT NULL,
+    "last_error" TEXT,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL
+);
+CREATE INDEX "index_jobs_on_type" ON "jobs" ("type");
+CREATE INDEX "index_jobs_on_priority_and_run_at" ON "jobs" ("priority", "run_at");
+"#,
+        down: r#"
+DROP TABLE "jobs";
+"#,
+    },
+    Migration {
+        version: "20180722101530",
+        name: "add_failed_at_to_jobs",
+        up: r#"
+ALTER TABLE "jobs" ADD COLUMN "failed_at" DATETIME;
+CREATE INDEX "index_jobs_on_failed_at" ON "jobs" ("failed_at");
+"#,
+        down: r#"
+DROP INDEX "index_jobs_on_failed_at";
+ALTER TABLE "jobs" DROP COLUMN "failed_at";
+"#,
+    },
+    Migration {
+        version: "20180805120000",
+        name: "add_request_id_to_logs",
+        up: r#"
+ALTER TABLE "logs" ADD COLUMN "request_id" VARCHAR(36);
+CREATE INDEX "index_logs_on_request_id" ON "logs" ("request_id");
+"#,
+        down: r#"
+DROP INDEX "index_logs_on_request_id";
+ALTER TABLE "logs" DROP COLUMN "request_id";
+"#,
+    },
+    Migration {
+        version: "20180806120000",
+        name: "widen_tinyint_columns",
+        // sqlite integers have no width, only mysql has tinyint
+        up: r#"
+"#,
+        down: r#"
+"#,
+    },
+];
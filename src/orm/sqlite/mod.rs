@@ -0,0 +1,55 @@
+pub mod migrations;
+pub mod schema;
+
+use std::result;
+
+use diesel::{
+    connection::SimpleConnection,
+    r2d2::{ConnectionManager, CustomizeConnection, Error},
+    sqlite::SqliteConnection,
+};
+use r2d2::Pool;
+
+use super::super::errors::Result;
+
+pub const DRIVER: &'static str = "sqlite";
+// id of the latest inserted row in this session
+pub const LAST_INSERT_ID: &'static str = "last_insert_rowid()";
+
+// show tables: .tables
+// show table schema: .schema xxx
+pub type Connection = SqliteConnection;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Config {
+    // path of the database file, ":memory:" for a throwaway database
+    pub file: String,
+}
+
+impl Config {
+    pub fn open(&self) -> Result<Pool<ConnectionManager<SqliteConnection>>> {
+        let builder = Pool::builder().connection_customizer(Box::new(Pragmas));
+        // every in-memory connection is a separate database
+        let builder = if self.file == MEMORY {
+            builder.max_size(1)
+        } else {
+            builder
+        };
+        Ok(builder.build(ConnectionManager::<SqliteConnection>::new(
+            &self.file[..],
+        ))?)
+    }
+}
+
+const MEMORY: &'static str = ":memory:";
+
+#[derive(Debug)]
+struct Pragmas;
+
+impl CustomizeConnection<SqliteConnection, Error> for Pragmas {
+    fn on_acquire(&self, con: &mut SqliteConnection) -> result::Result<(), Error> {
+        // wait for the writer instead of failing with "database is locked"
+        con.batch_execute("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+            .map_err(Error::QueryError)
+    }
+}
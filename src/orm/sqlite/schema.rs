@@ -0,0 +1,371 @@
+table! {
+    ar_internal_metadata (key) {
+        key -> Text,
+        value -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    attachments (id) {
+        id -> BigInt,
+        user_id -> BigInt,
+        name -> Text,
+        size -> Text,
+        mime_type -> Text,
+        url -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    cards (id) {
+        id -> BigInt,
+        title -> Text,
+        body -> Text,
+        media_type -> Text,
+        action -> Text,
+        href -> Text,
+        logo -> Text,
+        loc -> Text,
+        lang -> Text,
+        position -> SmallInt,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    caring_posts (id) {
+        id -> BigInt,
+        topic_id -> BigInt,
+        user_id -> BigInt,
+        method -> Text,
+        body -> Text,
+        media_type -> Text,
+        begin -> Timestamp,
+        end -> Timestamp,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    caring_topics (id) {
+        id -> BigInt,
+        user_id -> BigInt,
+        member_id -> BigInt,
+        tag -> Text,
+        name -> Text,
+        gender -> Text,
+        age -> SmallInt,
+        phone -> Nullable<Text>,
+        email -> Nullable<Text>,
+        address -> Nullable<Text>,
+        reason -> Text,
+        media_type -> Text,
+        status -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    forum_posts (id) {
+        id -> BigInt,
+        user_id -> BigInt,
+        topic_id -> BigInt,
+        post_id -> Nullable<BigInt>,
+        body -> Text,
+        media_type -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    forum_tags (id) {
+        id -> BigInt,
+        name -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    forum_topics (id) {
+        id -> BigInt,
+        user_id -> BigInt,
+        lang -> Text,
+        title -> Text,
+        body -> Text,
+        media_type -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    forum_topics_tags (id) {
+        id -> BigInt,
+        topic_id -> BigInt,
+        tag_id -> BigInt,
+    }
+}
+
+table! {
+    friend_links (id) {
+        id -> BigInt,
+        title -> Text,
+        home -> Text,
+        logo -> Text,
+        position -> SmallInt,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    jobs (id) {
+        id -> BigInt,
+        #[sql_name = "type"]
+        type_ -> Text,
+        content_type -> Text,
+        priority -> SmallInt,
+        payload -> Binary,
+        attempts -> SmallInt,
+        run_at -> Timestamp,
+        last_error -> Nullable<Text>,
+        failed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    leave_words (id) {
+        id -> BigInt,
+        body -> Text,
+        media_type -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    links (id) {
+        id -> BigInt,
+        href -> Text,
+        label -> Text,
+        loc -> Text,
+        lang -> Text,
+        x -> SmallInt,
+        y -> SmallInt,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    locales (id) {
+        id -> BigInt,
+        lang -> Text,
+        code -> Text,
+        message -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    logs (id) {
+        id -> BigInt,
+        user_id -> BigInt,
+        ip -> Text,
+        message -> Text,
+        created_at -> Timestamp,
+        request_id -> Nullable<Text>,
+    }
+}
+
+table! {
+    members (id) {
+        id -> BigInt,
+        nick_name -> Text,
+        real_name -> Text,
+        gender -> Text,
+        birthday -> Date,
+        phone -> Nullable<Text>,
+        email -> Nullable<Text>,
+        address -> Nullable<Text>,
+        line -> Nullable<Text>,
+        wechat -> Nullable<Text>,
+        skype -> Nullable<Text>,
+        weibo -> Nullable<Text>,
+        facebook -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    notifications (id) {
+        id -> BigInt,
+        user_id -> BigInt,
+        url -> Text,
+        body -> Text,
+        media_type -> Text,
+        level -> Text,
+        read -> Bool,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    policies (id) {
+        id -> BigInt,
+        user_id -> BigInt,
+        role -> Text,
+        resource -> Nullable<Text>,
+        nbf -> Date,
+        exp -> Date,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    schema_migrations (version) {
+        version -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    settings (id) {
+        id -> BigInt,
+        key -> Text,
+        value -> Binary,
+        salt -> Nullable<Binary>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    survey_fields (id) {
+        id -> BigInt,
+        form_id -> BigInt,
+        name -> Text,
+        label -> Text,
+        options -> Nullable<Text>,
+        #[sql_name = "type"]
+        type_ -> Text,
+        required -> Bool,
+        sort -> SmallInt,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    survey_forms (id) {
+        id -> BigInt,
+        user_id -> BigInt,
+        title -> Text,
+        body -> Text,
+        media_type -> Text,
+        uid -> Text,
+        mode -> Text,
+        nbf -> Date,
+        exp -> Date,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    survey_records (id) {
+        id -> BigInt,
+        field_id -> BigInt,
+        order -> Text,
+        value -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    survey_subscribers (id) {
+        id -> BigInt,
+        form_id -> BigInt,
+        email -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    users (id) {
+        id -> BigInt,
+        name -> Text,
+        email -> Text,
+        password -> Nullable<Binary>,
+        uid -> Text,
+        provider_type -> Text,
+        provider_id -> Text,
+        logo -> Text,
+        sign_in_count -> BigInt,
+        current_sign_in_at -> Nullable<Timestamp>,
+        current_sign_in_ip -> Nullable<Text>,
+        last_sign_in_at -> Nullable<Timestamp>,
+        last_sign_in_ip -> Nullable<Text>,
+        confirmed_at -> Nullable<Timestamp>,
+        locked_at -> Nullable<Timestamp>,
+        deleted_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    votes (id) {
+        id -> BigInt,
+        point -> BigInt,
+        resource_type -> Text,
+        resource_id -> BigInt,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(
+    ar_internal_metadata,
+    attachments,
+    cards,
+    caring_posts,
+    caring_topics,
+    forum_posts,
+    forum_tags,
+    forum_topics,
+    forum_topics_tags,
+    friend_links,
+    jobs,
+    leave_words,
+    links,
+    locales,
+    logs,
+    members,
+    notifications,
+    policies,
+    schema_migrations,
+    settings,
+    survey_fields,
+    survey_forms,
+    survey_records,
+    survey_subscribers,
+    users,
+    votes,
+);
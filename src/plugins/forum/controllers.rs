@@ -1,4 +1,291 @@
-#[get("/")]
-pub fn home() -> &'static str {
-    "Hello, arche!"
+use std::ops::Deref;
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rocket::{response::content::Html, State};
+
+use super::super::super::{
+    context::Context,
+    errors::Result,
+    orm::{
+        schema::{forum_posts, forum_tags, forum_topics, forum_topics_tags, users},
+        Connection, PooledConnection as Db,
+    },
+    request::Locale,
+    rfc::RFC822,
+    theme,
+};
+use super::super::nut::dao::layout;
+
+const PAGE_SIZE: i64 = 50;
+
+#[derive(Serialize, Debug)]
+struct Tag {
+    id: i64,
+    name: String,
+}
+
+#[derive(Serialize, Debug)]
+struct Topic {
+    id: i64,
+    title: String,
+    author: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, Debug)]
+struct Post {
+    id: i64,
+    body: String,
+    media_type: String,
+    author: String,
+    updated_at: String,
+}
+
+fn tags(db: &Connection) -> Result<Vec<Tag>> {
+    Ok(forum_tags::dsl::forum_tags
+        .select((forum_tags::dsl::id, forum_tags::dsl::name))
+        .order(forum_tags::dsl::name.asc())
+        .load::<(i64, String)>(db)?
+        .into_iter()
+        .map(|(id, name)| Tag { id: id, name: name })
+        .collect())
+}
+
+// the author may have been removed, which must not break the page
+fn author(name: Option<String>) -> String {
+    name.unwrap_or_else(|| "unknown".to_string())
+}
+
+#[derive(FromForm)]
+pub struct Pager {
+    page: Option<i64>,
+}
+
+impl Pager {
+    fn page(&self) -> i64 {
+        match self.page {
+            Some(v) if v > 1 => v,
+            _ => 1,
+        }
+    }
+}
+
+// one more row than the page size is loaded to tell whether there is a next page
+fn topics(
+    db: &Connection,
+    lang: &String,
+    tag: Option<i64>,
+    page: i64,
+) -> Result<(Vec<Topic>, bool)> {
+    let mut query = forum_topics::dsl::forum_topics
+        .left_join(users::dsl::users)
+        .select((
+            forum_topics::dsl::id,
+            users::dsl::name.nullable(),
+            forum_topics::dsl::title,
+            forum_topics::dsl::updated_at,
+        ))
+        .filter(forum_topics::dsl::lang.eq(lang))
+        .order((
+            forum_topics::dsl::updated_at.desc(),
+            forum_topics::dsl::id.desc(),
+        ))
+        .offset((page - 1) * PAGE_SIZE)
+        .limit(PAGE_SIZE + 1)
+        .into_boxed();
+    if let Some(tag) = tag {
+        query = query.filter(
+            forum_topics::dsl::id.eq_any(
+                forum_topics_tags::dsl::forum_topics_tags
+                    .select(forum_topics_tags::dsl::topic_id)
+                    .filter(forum_topics_tags::dsl::tag_id.eq(tag)),
+            ),
+        );
+    }
+    let mut items = query
+        .load::<(i64, Option<String>, String, NaiveDateTime)>(db)?
+        .into_iter()
+        .map(|(id, name, title, updated_at)| Topic {
+            id: id,
+            title: title,
+            author: author(name),
+            updated_at: updated_at.to_rfc822(),
+        })
+        .collect::<Vec<_>>();
+    let next = items.len() as i64 > PAGE_SIZE;
+    items.truncate(PAGE_SIZE as usize);
+    Ok((items, next))
+}
+
+fn render_home(lng: Locale, db: Db, ctx: State<Arc<Context>>, page: i64) -> Result<Html<String>> {
+    let Locale(lng) = lng;
+    let db = db.deref();
+    let (topics, next) = topics(db, &lng, None, page)?;
+    theme::render(
+        db,
+        &ctx,
+        "forum/index",
+        json!({
+            "layout": layout::get(db, &lng)?,
+            "title": t!(db, &lng, "forum.index.title"),
+            "tags": tags(db)?,
+            "topics": topics,
+            "page": page,
+            "next": next,
+        }),
+    )
+}
+
+#[get("/", rank = 2)]
+pub fn home(lng: Locale, db: Db, ctx: State<Arc<Context>>) -> Result<Html<String>> {
+    render_home(lng, db, ctx, 1)
+}
+
+#[get("/?<pager>", rank = 1)]
+pub fn home_by_page(
+    pager: Pager,
+    lng: Locale,
+    db: Db,
+    ctx: State<Arc<Context>>,
+) -> Result<Html<String>> {
+    render_home(lng, db, ctx, pager.page())
+}
+
+#[get("/topics/<id>")]
+pub fn topic(
+    id: i64,
+    lng: Locale,
+    db: Db,
+    ctx: State<Arc<Context>>,
+) -> Result<Option<Html<String>>> {
+    let Locale(lng) = lng;
+    let db = db.deref();
+    let (name, title, body, media_type, updated_at) = match forum_topics::dsl::forum_topics
+        .left_join(users::dsl::users)
+        .select((
+            users::dsl::name.nullable(),
+            forum_topics::dsl::title,
+            forum_topics::dsl::body,
+            forum_topics::dsl::media_type,
+            forum_topics::dsl::updated_at,
+        ))
+        .filter(forum_topics::dsl::id.eq(id))
+        .first::<(Option<String>, String, String, String, NaiveDateTime)>(db)
+        .optional()?
+    {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+
+    let tags = forum_tags::dsl::forum_tags
+        .select((forum_tags::dsl::id, forum_tags::dsl::name))
+        .filter(
+            forum_tags::dsl::id.eq_any(
+                forum_topics_tags::dsl::forum_topics_tags
+                    .select(forum_topics_tags::dsl::tag_id)
+                    .filter(forum_topics_tags::dsl::topic_id.eq(id)),
+            ),
+        )
+        .order(forum_tags::dsl::name.asc())
+        .load::<(i64, String)>(db)?
+        .into_iter()
+        .map(|(id, name)| Tag { id: id, name: name })
+        .collect::<Vec<_>>();
+
+    let posts = forum_posts::dsl::forum_posts
+        .left_join(users::dsl::users)
+        .select((
+            forum_posts::dsl::id,
+            users::dsl::name.nullable(),
+            forum_posts::dsl::body,
+            forum_posts::dsl::media_type,
+            forum_posts::dsl::updated_at,
+        ))
+        .filter(forum_posts::dsl::topic_id.eq(id))
+        .order(forum_posts::dsl::created_at.asc())
+        .load::<(i64, Option<String>, String, String, NaiveDateTime)>(db)?
+        .into_iter()
+        .map(|(id, name, body, media_type, updated_at)| Post {
+            id: id,
+            body: body,
+            media_type: media_type,
+            author: author(name),
+            updated_at: updated_at.to_rfc822(),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Some(theme::render(
+        db,
+        &ctx,
+        "forum/topic",
+        json!({
+            "layout": layout::get(db, &lng)?,
+            "topic": {
+                "id": id,
+                "title": title,
+                "body": body,
+                "media_type": media_type,
+                "author": author(name),
+                "updated_at": updated_at.to_rfc822(),
+            },
+            "tags": tags,
+            "posts": posts,
+        }),
+    )?))
+}
+
+fn render_tag(
+    id: i64,
+    lng: Locale,
+    db: Db,
+    ctx: State<Arc<Context>>,
+    page: i64,
+) -> Result<Option<Html<String>>> {
+    let Locale(lng) = lng;
+    let db = db.deref();
+    let name = match forum_tags::dsl::forum_tags
+        .select(forum_tags::dsl::name)
+        .filter(forum_tags::dsl::id.eq(id))
+        .first::<String>(db)
+        .optional()?
+    {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let (topics, next) = topics(db, &lng, Some(id), page)?;
+    Ok(Some(theme::render(
+        db,
+        &ctx,
+        "forum/tag",
+        json!({
+            "layout": layout::get(db, &lng)?,
+            "tag": Tag { id: id, name: name },
+            "topics": topics,
+            "page": page,
+            "next": next,
+        }),
+    )?))
+}
+
+#[get("/tags/<id>", rank = 2)]
+pub fn tag(
+    id: i64,
+    lng: Locale,
+    db: Db,
+    ctx: State<Arc<Context>>,
+) -> Result<Option<Html<String>>> {
+    render_tag(id, lng, db, ctx, 1)
+}
+
+#[get("/tags/<id>?<pager>", rank = 1)]
+pub fn tag_by_page(
+    id: i64,
+    pager: Pager,
+    lng: Locale,
+    db: Db,
+    ctx: State<Arc<Context>>,
+) -> Result<Option<Html<String>>> {
+    render_tag(id, lng, db, ctx, pager.page())
 }
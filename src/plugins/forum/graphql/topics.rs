@@ -8,7 +8,10 @@ use validator::Validate;
 use super::super::super::super::{
     errors::{Error, Result},
     graphql::{context::Context, H},
-    orm::schema::{forum_posts, forum_tags, forum_topics, forum_topics_tags},
+    orm::{
+        last_insert_id,
+        schema::{forum_posts, forum_tags, forum_topics, forum_topics_tags},
+    },
     rfc::UtcDateTime,
 };
 use super::super::dao;
@@ -167,7 +170,7 @@ impl Create {
         let user = ctx.current_user()?;
         db.transaction::<_, Error, _>(|| {
             let now = Utc::now().naive_utc();
-            let id = insert_into(forum_topics::dsl::forum_topics)
+            insert_into(forum_topics::dsl::forum_topics)
                 .values((
                     forum_topics::dsl::user_id.eq(&user.id),
                     forum_topics::dsl::lang.eq(&ctx.locale),
@@ -177,8 +180,8 @@ impl Create {
                     forum_topics::dsl::updated_at.eq(&now),
                     forum_topics::dsl::created_at.eq(&now),
                 ))
-                .returning(forum_topics::dsl::id)
-                .get_result::<i64>(db)?;
+                .execute(db)?;
+            let id = last_insert_id(db)?;
             for t in self.tags.iter() {
                 insert_into(forum_topics_tags::dsl::forum_topics_tags)
                     .values((
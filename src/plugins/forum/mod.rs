@@ -2,32 +2,182 @@ pub mod controllers;
 pub mod dao;
 pub mod graphql;
 
-use chrono::Utc;
+use chrono::NaiveDateTime;
+use diesel::{dsl::max, prelude::*};
 use rocket::Route;
 use sitemap::structs::ChangeFreq;
 
-use super::super::{RssItem, SitemapItem};
+use super::super::{
+    errors::Result,
+    orm::{
+        schema::{forum_tags, forum_topics, forum_topics_tags},
+        Connection as Db,
+    },
+    seo::{Section, FEED_MAX_ITEMS},
+    RobotsItem, RssItem, SitemapItem,
+};
 
 pub const NAME: &'static str = "forum";
 
 pub fn routes() -> Vec<(&'static str, Vec<Route>)> {
-    vec![("/forum", routes![controllers::home])]
-}
-
-pub fn sitemap() -> Vec<SitemapItem> {
     vec![(
-        "/forum".to_string(),
-        0.1,
-        ChangeFreq::Daily,
-        Utc::now().naive_utc(),
+        "/forum",
+        routes![
+            controllers::home,
+            controllers::home_by_page,
+            controllers::topic,
+            controllers::tag,
+            controllers::tag_by_page
+        ],
     )]
 }
 
-pub fn rss(_lang: &String) -> Vec<RssItem> {
+pub fn robots() -> Vec<RobotsItem> {
     vec![(
-        "/forum/posts/1".to_string(),
-        "post title 1".to_string(),
-        "post body 1".to_string(),
-        Utc::now().naive_utc(),
+        "*".to_string(),
+        vec!["/forum/".to_string()],
+        vec![],
     )]
 }
+
+pub fn sitemap() -> Vec<Section> {
+    vec![
+        Section {
+            count: home_count,
+            load: home_page,
+        },
+        Section {
+            count: topics_count,
+            load: topics_page,
+        },
+        Section {
+            count: tags_count,
+            load: tags_page,
+        },
+    ]
+}
+
+fn home_last(db: &Db) -> Result<Option<NaiveDateTime>> {
+    Ok(forum_topics::dsl::forum_topics
+        .select(max(forum_topics::dsl::updated_at))
+        .first::<Option<NaiveDateTime>>(db)?)
+}
+
+fn home_count(db: &Db) -> Result<i64> {
+    Ok(match home_last(db)? {
+        Some(_) => 1,
+        None => 0,
+    })
+}
+
+fn home_page(db: &Db, _offset: i64, _limit: i64) -> Result<Vec<SitemapItem>> {
+    Ok(match home_last(db)? {
+        Some(last) => vec![("/forum".to_string(), 0.8, ChangeFreq::Daily, last)],
+        None => Vec::new(),
+    })
+}
+
+fn topics_count(db: &Db) -> Result<i64> {
+    Ok(forum_topics::dsl::forum_topics
+        .count()
+        .get_result::<i64>(db)?)
+}
+
+fn topics_page(db: &Db, offset: i64, limit: i64) -> Result<Vec<SitemapItem>> {
+    Ok(forum_topics::dsl::forum_topics
+        .select((forum_topics::dsl::id, forum_topics::dsl::updated_at))
+        .order(forum_topics::dsl::id.asc())
+        .offset(offset)
+        .limit(limit)
+        .load::<(i64, NaiveDateTime)>(db)?
+        .into_iter()
+        .map(|(id, updated_at)| {
+            (
+                format!("/forum/topics/{}", id),
+                0.6,
+                ChangeFreq::Weekly,
+                updated_at,
+            )
+        })
+        .collect())
+}
+
+fn tags_count(db: &Db) -> Result<i64> {
+    Ok(forum_tags::dsl::forum_tags.count().get_result::<i64>(db)?)
+}
+
+fn tags_page(db: &Db, offset: i64, limit: i64) -> Result<Vec<SitemapItem>> {
+    Ok(forum_tags::dsl::forum_tags
+        .select((forum_tags::dsl::id, forum_tags::dsl::updated_at))
+        .order(forum_tags::dsl::id.asc())
+        .offset(offset)
+        .limit(limit)
+        .load::<(i64, NaiveDateTime)>(db)?
+        .into_iter()
+        .map(|(id, updated_at)| {
+            (
+                format!("/forum/tags/{}", id),
+                0.4,
+                ChangeFreq::Weekly,
+                updated_at,
+            )
+        })
+        .collect())
+}
+
+fn topic_item(it: (i64, String, String, NaiveDateTime)) -> RssItem {
+    let (id, title, body, updated_at) = it;
+    (format!("/forum/topics/{}", id), title, body, updated_at)
+}
+
+pub fn rss(db: &Db, lang: &String) -> Result<Vec<RssItem>> {
+    Ok(forum_topics::dsl::forum_topics
+        .select((
+            forum_topics::dsl::id,
+            forum_topics::dsl::title,
+            forum_topics::dsl::body,
+            forum_topics::dsl::updated_at,
+        ))
+        .filter(forum_topics::dsl::lang.eq(lang))
+        .order(forum_topics::dsl::updated_at.desc())
+        .limit(FEED_MAX_ITEMS as i64)
+        .load::<(i64, String, String, NaiveDateTime)>(db)?
+        .into_iter()
+        .map(topic_item)
+        .collect())
+}
+
+// tag name and its latest topics, none if the tag does not exist
+pub fn tag_rss(db: &Db, lang: &String, tag: i64) -> Result<Option<(String, Vec<RssItem>)>> {
+    let name = match forum_tags::dsl::forum_tags
+        .select(forum_tags::dsl::name)
+        .filter(forum_tags::dsl::id.eq(tag))
+        .first::<String>(db)
+        .optional()?
+    {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let items = forum_topics::dsl::forum_topics
+        .select((
+            forum_topics::dsl::id,
+            forum_topics::dsl::title,
+            forum_topics::dsl::body,
+            forum_topics::dsl::updated_at,
+        ))
+        .filter(forum_topics::dsl::lang.eq(lang))
+        .filter(
+            forum_topics::dsl::id.eq_any(
+                forum_topics_tags::dsl::forum_topics_tags
+                    .select(forum_topics_tags::dsl::topic_id)
+                    .filter(forum_topics_tags::dsl::tag_id.eq(tag)),
+            ),
+        )
+        .order(forum_topics::dsl::updated_at.desc())
+        .limit(FEED_MAX_ITEMS as i64)
+        .load::<(i64, String, String, NaiveDateTime)>(db)?
+        .into_iter()
+        .map(topic_item)
+        .collect();
+    Ok(Some((name, items)))
+}
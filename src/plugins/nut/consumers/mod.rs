@@ -1 +1,2 @@
+pub mod purge_logs;
 pub mod send_mail;
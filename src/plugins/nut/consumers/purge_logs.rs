@@ -0,0 +1,37 @@
+use std::ops::Deref;
+
+use chrono::{Duration, Utc};
+use diesel::{delete, prelude::*};
+use log;
+use serde_json;
+
+use super::super::super::super::{context::Context, errors::Result, orm::schema::logs, queue};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Purge {
+    // keep logs of the latest days
+    pub days: i64,
+}
+
+pub const NAME: &'static str = "purge-logs";
+
+pub struct Consumer {}
+
+impl queue::Consumer for Consumer {
+    fn consume(
+        &self,
+        ctx: &Context,
+        _id: &String,
+        _content_type: &String,
+        _priority: u8,
+        payload: &[u8],
+    ) -> Result<()> {
+        let it: Purge = serde_json::from_slice(payload)?;
+        let db = ctx.db.get()?;
+        let db = db.deref();
+        let before = Utc::now().naive_utc() - Duration::days(it.days);
+        let cnt = delete(logs::dsl::logs.filter(logs::dsl::created_at.lt(&before))).execute(db)?;
+        log::info!("remove {} logs before {}", cnt, before);
+        Ok(())
+    }
+}
@@ -1,6 +1,192 @@
-use super::super::super::request::Locale;
+use std::fmt::Display;
+use std::io::Read;
+use std::ops::Deref;
+use std::result::Result as StdResult;
+use std::sync::Arc;
+
+use chrono::Utc;
+use diesel::{insert_into, prelude::*};
+use log;
+use multipart::server::Multipart;
+use rocket::{
+    http::{ContentType, Status},
+    response::{content::Html, status},
+    Data, State,
+};
+use rocket_contrib::Json;
+
+use super::super::super::{
+    context::Context,
+    errors::{Error, Result},
+    graphql::context::current_user,
+    orm::{last_insert_id, schema::attachments, Connection, PooledConnection as Db},
+    request::{Locale, Token},
+    rfc::UtcDateTime,
+    storage, theme,
+};
+use super::dao::layout;
+use super::graphql::attachments::Attachment;
+
+// files in one upload request
+const MAX_PARTS: usize = 16;
 
 #[get("/")]
-pub fn home(lng: Locale) -> String {
-    format!("Hello, arche {:?}!", lng)
+pub fn home(lng: Locale, db: Db, ctx: State<Arc<Context>>) -> Result<Html<String>> {
+    let Locale(lng) = lng;
+    let db = db.deref();
+    theme::render(
+        db,
+        &ctx,
+        "nut/home",
+        json!({
+            "layout": layout::get(db, &lng)?,
+        }),
+    )
+}
+
+fn failed<E: Display>(status: Status, e: E) -> status::Custom<String> {
+    status::Custom(status, e.to_string())
+}
+
+// all the files are checked before anything is stored
+fn read_files<R: Read>(
+    body: R,
+    boundary: &str,
+    limit: u64,
+) -> StdResult<Vec<(String, String, Vec<u8>)>, status::Custom<String>> {
+    let mut form = Multipart::with_body(body, boundary);
+    let mut items = Vec::new();
+    let mut parts = 0;
+    loop {
+        let mut field = match form.read_entry() {
+            Ok(Some(v)) => v,
+            Ok(None) => break,
+            Err(e) => return Err(failed(Status::BadRequest, e)),
+        };
+        parts += 1;
+        if parts > MAX_PARTS {
+            return Err(failed(
+                Status::PayloadTooLarge,
+                format!("at most {} parts in one request", MAX_PARTS),
+            ));
+        }
+        let name = match field.headers.filename {
+            Some(ref v) => v.clone(),
+            None => continue,
+        };
+        let mut body = Vec::new();
+        if let Err(e) = field.data.by_ref().take(limit + 1).read_to_end(&mut body) {
+            return Err(failed(Status::BadRequest, e));
+        }
+        if body.len() as u64 > limit {
+            return Err(failed(
+                Status::PayloadTooLarge,
+                format!("{} is larger than {} bytes", name, limit),
+            ));
+        }
+        let mime_type = storage::content_type(&name, &body);
+        if storage::is_active(&mime_type) {
+            return Err(failed(
+                Status::UnsupportedMediaType,
+                format!("{} is not allowed", mime_type),
+            ));
+        }
+        items.push((name, mime_type, body));
+    }
+    Ok(items)
+}
+
+// all or nothing, stored objects are removed again if any of them fails
+fn save(
+    db: &Connection,
+    ctx: &Context,
+    user: i64,
+    files: Vec<(String, String, Vec<u8>)>,
+) -> Result<Vec<Attachment>> {
+    let mut keys = Vec::new();
+    let ret = db.transaction::<_, Error, _>(|| {
+        let mut items = Vec::new();
+        for (name, mime_type, body) in files {
+            let key = ctx.storage.put(&mime_type, body.as_slice())?;
+            keys.push(key.clone());
+            let size = body.len().to_string();
+            let now = Utc::now().naive_utc();
+            // keep the storage key, urls are resolved by the provider when listing
+            insert_into(attachments::dsl::attachments)
+                .values((
+                    attachments::dsl::user_id.eq(&user),
+                    attachments::dsl::name.eq(&name),
+                    attachments::dsl::size.eq(&size),
+                    attachments::dsl::mime_type.eq(&mime_type),
+                    attachments::dsl::url.eq(&key),
+                    attachments::dsl::updated_at.eq(&now),
+                    attachments::dsl::created_at.eq(&now),
+                ))
+                .execute(db)?;
+            items.push(Attachment {
+                id: last_insert_id(db)?.to_string(),
+                name: name,
+                size: size,
+                mime_type: mime_type,
+                url: ctx.storage.url(&key)?,
+                updated_at: now.to_utc(),
+            });
+        }
+        Ok(items)
+    });
+    if ret.is_err() {
+        // don't leave objects nobody could find
+        for key in keys {
+            if let Err(e) = ctx.storage.delete(&key) {
+                log::error!("delete {} {:?}", key, e);
+            }
+        }
+    }
+    ret
+}
+
+#[post("/upload", data = "<data>")]
+pub fn upload(
+    cnt: ContentType,
+    data: Data,
+    token: Token,
+    lng: Locale,
+    db: Db,
+    ctx: State<Arc<Context>>,
+) -> StdResult<Json<Vec<Attachment>>, status::Custom<String>> {
+    let Token(token) = token;
+    let Locale(lng) = lng;
+    let db = db.deref();
+    let user = match token {
+        Some(ref t) => match current_user(db, &ctx.jwt, &lng, t) {
+            Ok(v) => v,
+            Err(e) => return Err(failed(Status::Unauthorized, e)),
+        },
+        None => return Err(failed(Status::Unauthorized, Status::Unauthorized.reason)),
+    };
+    if !cnt.is_form_data() {
+        return Err(failed(
+            Status::UnsupportedMediaType,
+            Status::UnsupportedMediaType.reason,
+        ));
+    }
+    let boundary = match cnt.params().find(|&(k, _)| k == "boundary") {
+        Some((_, v)) => v,
+        None => return Err(failed(Status::BadRequest, Status::BadRequest.reason)),
+    };
+
+    // the limit applies to each file and to the whole request body
+    let limit = ctx.config.http.upload_limits();
+    let mut body = data.open().take(limit + 1);
+    let files = read_files(&mut body, boundary, limit);
+    if body.limit() == 0 {
+        return Err(failed(
+            Status::PayloadTooLarge,
+            format!("the request is larger than {} bytes", limit),
+        ));
+    }
+    match save(db, &ctx, user.id, files?) {
+        Ok(v) => Ok(Json(v)),
+        Err(e) => Err(failed(Status::InternalServerError, e)),
+    }
 }
@@ -0,0 +1,141 @@
+use diesel::prelude::*;
+
+use super::super::super::super::{
+    errors::Result,
+    i18n,
+    orm::{
+        schema::{cards, friend_links, links},
+        Connection as Db,
+    },
+};
+
+#[derive(Serialize, Debug)]
+pub struct Site {
+    pub title: String,
+    pub subhead: String,
+    pub keywords: String,
+    pub description: String,
+    pub copyright: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Link {
+    pub href: String,
+    pub label: String,
+    pub loc: String,
+    pub x: i16,
+    pub y: i16,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Card {
+    pub title: String,
+    pub body: String,
+    pub media_type: String,
+    pub action: String,
+    pub href: String,
+    pub logo: String,
+    pub loc: String,
+    pub position: i16,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FriendLink {
+    pub title: String,
+    pub home: String,
+    pub logo: String,
+}
+
+// shared by every server-rendered page, templates pick links and cards by loc
+#[derive(Serialize, Debug)]
+pub struct Layout {
+    pub lang: String,
+    pub languages: Vec<String>,
+    pub site: Site,
+    pub links: Vec<Link>,
+    pub cards: Vec<Card>,
+    pub friend_links: Vec<FriendLink>,
+}
+
+pub fn get(db: &Db, lang: &String) -> Result<Layout> {
+    let links = links::dsl::links
+        .select((
+            links::dsl::href,
+            links::dsl::label,
+            links::dsl::loc,
+            links::dsl::x,
+            links::dsl::y,
+        ))
+        .filter(links::dsl::lang.eq(lang))
+        .order((links::dsl::loc.asc(), links::dsl::x.asc(), links::dsl::y.asc()))
+        .load::<(String, String, String, i16, i16)>(db)?
+        .into_iter()
+        .map(|(href, label, loc, x, y)| Link {
+            href: href,
+            label: label,
+            loc: loc,
+            x: x,
+            y: y,
+        })
+        .collect();
+
+    let cards = cards::dsl::cards
+        .select((
+            cards::dsl::title,
+            cards::dsl::body,
+            cards::dsl::media_type,
+            cards::dsl::action,
+            cards::dsl::href,
+            cards::dsl::logo,
+            cards::dsl::loc,
+            cards::dsl::position,
+        ))
+        .filter(cards::dsl::lang.eq(lang))
+        .order((cards::dsl::loc.asc(), cards::dsl::position.asc()))
+        .load::<(String, String, String, String, String, String, String, i16)>(db)?
+        .into_iter()
+        .map(
+            |(title, body, media_type, action, href, logo, loc, position)| Card {
+                title: title,
+                body: body,
+                media_type: media_type,
+                action: action,
+                href: href,
+                logo: logo,
+                loc: loc,
+                position: position,
+            },
+        )
+        .collect();
+
+    let friend_links = friend_links::dsl::friend_links
+        .select((
+            friend_links::dsl::title,
+            friend_links::dsl::home,
+            friend_links::dsl::logo,
+        ))
+        .order(friend_links::dsl::position.asc())
+        .load::<(String, String, String)>(db)?
+        .into_iter()
+        .map(|(title, home, logo)| FriendLink {
+            title: title,
+            home: home,
+            logo: logo,
+        })
+        .collect();
+
+    Ok(Layout {
+        lang: lang.clone(),
+        languages: i18n::languages(db)?,
+        site: Site {
+            title: t!(db, lang, "site.title"),
+            subhead: t!(db, lang, "site.subhead"),
+            keywords: t!(db, lang, "site.keywords"),
+            description: t!(db, lang, "site.description"),
+            copyright: t!(db, lang, "site.copyright"),
+        },
+        links: links,
+        cards: cards,
+        friend_links: friend_links,
+    })
+}
@@ -3,19 +3,26 @@ use diesel::{insert_into, prelude::*};
 
 use super::super::super::super::{
     errors::Result,
-    orm::{schema::logs, Connection as Db},
+    orm::{last_insert_id, schema::logs, Connection as Db},
 };
 
-pub fn add(db: &Db, user: &i64, ip: &String, message: &String) -> Result<i64> {
+pub fn add(
+    db: &Db,
+    user: &i64,
+    ip: &String,
+    request_id: &String,
+    message: &String,
+) -> Result<i64> {
     let now = Utc::now().naive_utc();
-    let id = insert_into(logs::dsl::logs)
+    insert_into(logs::dsl::logs)
         .values((
             logs::dsl::user_id.eq(user),
             logs::dsl::ip.eq(ip),
             logs::dsl::message.eq(message),
+            logs::dsl::request_id.eq(&Some(request_id)),
             logs::dsl::created_at.eq(&now),
         ))
-        .returning(logs::dsl::id)
-        .get_result::<i64>(db)?;
+        .execute(db)?;
+    let id = last_insert_id(db)?;
     Ok(id)
 }
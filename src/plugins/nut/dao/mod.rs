@@ -1,3 +1,4 @@
+pub mod layout;
 pub mod log;
 pub mod policy;
 pub mod user;
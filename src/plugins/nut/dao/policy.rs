@@ -3,7 +3,7 @@ use diesel::{delete, insert_into, prelude::*, update};
 
 use super::super::super::super::{
     errors::Result,
-    orm::{schema::policies, Connection as Db},
+    orm::{last_insert_id, schema::policies, Connection as Db},
 };
 use super::super::models::{Policy, Role};
 
@@ -89,7 +89,7 @@ pub fn apply(
             Ok(id)
         }
         Err(_) => {
-            let id = insert_into(policies::dsl::policies)
+            insert_into(policies::dsl::policies)
                 .values((
                     policies::dsl::user_id.eq(user),
                     policies::dsl::role.eq(&role),
@@ -99,8 +99,8 @@ pub fn apply(
                     policies::dsl::updated_at.eq(&now),
                     policies::dsl::created_at.eq(&now),
                 ))
-                .returning(policies::dsl::id)
-                .get_result::<i64>(db)?;
+                .execute(db)?;
+            let id = last_insert_id(db)?;
             Ok(id)
         }
     }
@@ -8,7 +8,7 @@ use uuid::Uuid;
 
 use super::super::super::super::{
     errors::Result,
-    orm::{schema::users, Connection as Db},
+    orm::{last_insert_id, schema::users, Connection as Db},
     utils,
 };
 
@@ -106,7 +106,7 @@ pub fn add_by_email(
     let uid = Uuid::new_v4().to_string();
 
     let password = utils::hash::sum(password.as_bytes())?;
-    let id = insert_into(users::dsl::users)
+    insert_into(users::dsl::users)
         .values((
             users::dsl::email.eq(email),
             users::dsl::name.eq(name),
@@ -119,8 +119,8 @@ pub fn add_by_email(
             users::dsl::updated_at.eq(&now),
             users::dsl::created_at.eq(&now),
         ))
-        .returning(users::dsl::id)
-        .get_result::<i64>(db)?;
+        .execute(db)?;
+    let id = last_insert_id(db)?;
     Ok((id, uid))
 }
 
@@ -0,0 +1,93 @@
+use std::ops::Deref;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::{delete, prelude::*};
+use log;
+use rocket::http::Status;
+use validator::Validate;
+
+use super::super::super::super::{
+    errors::{Error, Result},
+    graphql::{context::Context, H},
+    orm::schema::attachments,
+    rfc::UtcDateTime,
+};
+use super::super::{dao, models::Role};
+
+#[derive(GraphQLObject, Debug, Serialize)]
+pub struct Attachment {
+    pub id: String,
+    pub name: String,
+    pub size: String,
+    pub mime_type: String,
+    pub url: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(GraphQLInputObject, Debug, Validate, Deserialize)]
+pub struct Remove {
+    #[validate(length(min = "1"))]
+    pub id: String,
+}
+
+impl Remove {
+    pub fn call(&self, ctx: &Context) -> Result<H> {
+        self.validate()?;
+        let id: i64 = self.id.parse()?;
+        let user = ctx.current_user()?;
+        let db = ctx.db.deref();
+        let (user_id, key) = attachments::dsl::attachments
+            .select((attachments::dsl::user_id, attachments::dsl::url))
+            .filter(attachments::dsl::id.eq(&id))
+            .first::<(i64, String)>(db)?;
+        if user_id != user.id && !dao::policy::is(db, &user.id, &Role::Admin) {
+            return Err(Status::Forbidden.reason.into());
+        }
+        let it = attachments::dsl::attachments.filter(attachments::dsl::id.eq(&id));
+        // the row goes first, the object may already be gone
+        db.transaction::<_, Error, _>(|| {
+            delete(it).execute(db)?;
+            match ctx.app.storage.delete(&key) {
+                Ok(_) => Ok(()),
+                Err(e) => match ctx.app.storage.exists(&key) {
+                    Ok(false) => {
+                        log::warn!("attachment {} is gone already {:?}", key, e);
+                        Ok(())
+                    }
+                    _ => Err(e),
+                },
+            }
+        })?;
+        Ok(H::new())
+    }
+}
+
+pub fn list(ctx: &Context) -> Result<Vec<Attachment>> {
+    let user = ctx.current_user()?;
+    let db = ctx.db.deref();
+    let items = attachments::dsl::attachments
+        .select((
+            attachments::dsl::id,
+            attachments::dsl::name,
+            attachments::dsl::size,
+            attachments::dsl::mime_type,
+            attachments::dsl::url,
+            attachments::dsl::updated_at,
+        ))
+        .filter(attachments::dsl::user_id.eq(&user.id))
+        .order(attachments::dsl::updated_at.desc())
+        .load::<(i64, String, String, String, String, NaiveDateTime)>(db)?;
+
+    let mut attachments = Vec::new();
+    for (id, name, size, mime_type, key, updated_at) in items {
+        attachments.push(Attachment {
+            id: id.to_string(),
+            name: name,
+            size: size,
+            mime_type: mime_type,
+            url: ctx.app.storage.url(&key)?,
+            updated_at: updated_at.to_utc(),
+        });
+    }
+    Ok(attachments)
+}
@@ -35,6 +35,7 @@ impl Install {
                 db,
                 &user,
                 &ctx.client_ip,
+                &ctx.request_id,
                 &ctx.locale,
                 "nut.logs.user.sign-up"
             )?;
@@ -43,6 +44,7 @@ impl Install {
                 db,
                 &user,
                 &ctx.client_ip,
+                &ctx.request_id,
                 &ctx.locale,
                 "nut.logs.user.confirm"
             )?;
@@ -55,6 +57,7 @@ impl Install {
                     db,
                     &user,
                     &ctx.client_ip,
+                    &ctx.request_id,
                     &ctx.locale,
                     "nut.logs.role.apply",
                     &Some(json!({
@@ -8,6 +8,7 @@ use super::super::super::super::{
     errors::Result,
     graphql::{context::Context, H},
     orm::schema::*,
+    rate_limit,
     rfc::UtcDateTime,
 };
 
@@ -72,6 +73,7 @@ pub struct Create {
 impl Create {
     pub fn call(&self, ctx: &Context) -> Result<H> {
         self.validate()?;
+        ctx.rate_limit(rate_limit::LEAVE_WORD, None)?;
         let db = ctx.db.deref();
         let now = Utc::now().naive_utc();
         insert_into(leave_words::dsl::leave_words)
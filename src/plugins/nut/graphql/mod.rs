@@ -1,3 +1,4 @@
+pub mod attachments;
 pub mod cards;
 pub mod friend_links;
 pub mod home;
@@ -1,5 +1,7 @@
 use super::super::super::super::super::oauth::{self, Oauth as OauthUrl};
 
+pub const ROBOTS: &'static str = "site.robots";
+
 #[derive(GraphQLObject, Debug, Deserialize, Serialize)]
 pub struct Smtp {
     pub host: String,
@@ -14,6 +16,32 @@ pub struct Seo {
     pub baidu: String,
 }
 
+// https://developers.google.com/search/reference/robots_txt
+#[derive(GraphQLObject, Debug, Deserialize, Serialize, Clone)]
+pub struct RobotsRule {
+    pub user_agent: String,
+    pub allow: Vec<String>,
+    pub disallow: Vec<String>,
+}
+
+#[derive(GraphQLObject, Debug, Deserialize, Serialize)]
+pub struct ThemeSetting {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(GraphQLObject, Debug, Deserialize, Serialize)]
+pub struct Theme {
+    // folder under themes/
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub layouts: Vec<String>,
+    pub settings: Vec<ThemeSetting>,
+    pub active: bool,
+}
+
 #[derive(GraphQLObject, Debug, Deserialize, Serialize)]
 pub struct Status {
     pub name: String,
@@ -6,10 +6,10 @@ use validator::Validate;
 use super::super::super::super::super::{
     errors::{Error, Result},
     graphql::{context::Context, H},
-    i18n, settings,
+    i18n, settings, theme,
 };
 use super::super::super::consumers::send_mail::Config as SmtpConfig;
-use super::models::Author;
+use super::models::{Author, RobotsRule, ROBOTS};
 
 #[derive(GraphQLInputObject, Debug, Validate, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -75,6 +75,74 @@ impl UpdateSeo {
     }
 }
 
+#[derive(GraphQLInputObject, Debug, Validate, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRobotsRule {
+    #[validate(length(min = "1"))]
+    pub user_agent: String,
+    pub allow: Vec<String>,
+    pub disallow: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRobots {
+    pub rules: Vec<UpdateRobotsRule>,
+}
+
+impl UpdateRobots {
+    pub fn call(&self, ctx: &Context) -> Result<H> {
+        ctx.admin()?;
+        let mut items = Vec::new();
+        for it in self.rules.iter() {
+            it.validate()?;
+            let user_agent = it.user_agent.trim();
+            // a newline would inject extra lines into robots.txt
+            if user_agent.is_empty() || !is_robots_token(user_agent) {
+                return Err(format!("bad robots user agent {:?}", it.user_agent).into());
+            }
+            for path in it.allow.iter().chain(it.disallow.iter()) {
+                if !path.starts_with('/') || !is_robots_token(path) {
+                    return Err(format!("bad robots path {:?}", path).into());
+                }
+            }
+            items.push(RobotsRule {
+                user_agent: user_agent.to_string(),
+                allow: it.allow.clone(),
+                disallow: it.disallow.clone(),
+            });
+        }
+        let db = ctx.db.deref();
+        settings::set(db, &ctx.app.encryptor, &ROBOTS.to_string(), &items, false)?;
+        Ok(H::new())
+    }
+}
+
+fn is_robots_token(s: &str) -> bool {
+    !s.chars().any(|c| c.is_control() || c.is_whitespace())
+}
+
+#[derive(GraphQLInputObject, Debug, Validate, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTheme {
+    #[validate(length(min = "1"))]
+    pub id: String,
+}
+
+impl UpdateTheme {
+    pub fn call(&self, ctx: &Context) -> Result<H> {
+        self.validate()?;
+        ctx.admin()?;
+        if !theme::list()?.iter().any(|(id, _)| *id == self.id) {
+            return Err(format!("can't find theme {}", self.id).into());
+        }
+        // fails on a broken theme, before it's picked
+        ctx.app.themes.load(&self.id)?;
+        let db = ctx.db.deref();
+        settings::set(db, &ctx.app.encryptor, &theme::KEY.to_string(), &self.id, false)?;
+        Ok(H::new())
+    }
+}
+
 #[derive(GraphQLInputObject, Debug, Validate, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateAuthor {
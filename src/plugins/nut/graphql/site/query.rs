@@ -1,10 +1,12 @@
 use std::ops::Deref;
 
 use super::super::super::super::super::{
-    env, errors::Result, graphql::context::Context, i18n, settings, sys,
+    env, errors::Result, graphql::context::Context, i18n, settings, sys, theme,
 };
 use super::super::super::consumers::send_mail::Config as SmtpConfig;
-use super::models::{Author, Info, Seo, Smtp, Status};
+use super::models::{
+    Author, Info, RobotsRule, Seo, Smtp, Status, Theme, ThemeSetting, ROBOTS,
+};
 
 pub fn smtp(ctx: &Context) -> Result<Smtp> {
     ctx.admin()?;
@@ -27,6 +29,37 @@ pub fn seo(ctx: &Context) -> Result<Seo> {
     })
 }
 
+pub fn robots(ctx: &Context) -> Result<Vec<RobotsRule>> {
+    ctx.admin()?;
+    let db = ctx.db.deref();
+    match settings::get(db, &ctx.app.encryptor, &ROBOTS.to_string()) {
+        Ok(v) => Ok(v),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+pub fn themes(ctx: &Context) -> Result<Vec<Theme>> {
+    ctx.admin()?;
+    let db = ctx.db.deref();
+    let active = theme::current(db, &ctx.app);
+    Ok(theme::list()?
+        .into_iter()
+        .map(|(id, it)| Theme {
+            active: id == active,
+            id: id,
+            name: it.name,
+            version: it.version,
+            description: it.description,
+            layouts: it.layouts,
+            settings: it
+                .settings
+                .into_iter()
+                .map(|(k, v)| ThemeSetting { key: k, value: v })
+                .collect(),
+        })
+        .collect())
+}
+
 pub fn author(ctx: &Context) -> Result<Author> {
     let db = ctx.db.deref();
     settings::get(db, &ctx.app.encryptor, &"site.author".to_string())
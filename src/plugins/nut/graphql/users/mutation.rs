@@ -12,7 +12,7 @@ use super::super::super::super::super::{
     i18n,
     jwt::Jwt,
     orm::{schema::*, Connection as Db},
-    queue, utils,
+    queue, rate_limit, utils,
 };
 use super::super::super::{consumers, dao, models::Role};
 use super::models::{Policy, SignIn};
@@ -48,6 +48,7 @@ impl UpdatePolicy {
                 db,
                 &user,
                 &ctx.client_ip,
+                &ctx.request_id,
                 &ctx.locale,
                 "nut.logs.role.clear"
             )?;
@@ -60,6 +61,7 @@ impl UpdatePolicy {
                     db,
                     &user,
                     &ctx.client_ip,
+                    &ctx.request_id,
                     &ctx.locale,
                     "nut.logs.role.apply",
                     &Some(json!({
@@ -103,6 +105,7 @@ impl ChangePassword {
                         db,
                         &user.id,
                         &ctx.client_ip,
+                        &ctx.request_id,
                         &ctx.locale,
                         "nut.logs.user.change-password"
                     )?;
@@ -143,6 +146,7 @@ impl UpdateProfile {
                 db,
                 &user.id,
                 &ctx.client_ip,
+                &ctx.request_id,
                 &ctx.locale,
                 "nut.logs.user.update-profile"
             )?;
@@ -158,6 +162,7 @@ pub fn sign_out(ctx: &Context) -> Result<H> {
         ctx.db.deref(),
         &it.id,
         &ctx.client_ip,
+        &ctx.request_id,
         &ctx.locale,
         "nut.logs.user.sign-out"
     )?;
@@ -175,6 +180,7 @@ pub struct SignInByEmail {
 impl SignInByEmail {
     pub fn call(&self, ctx: &Context) -> Result<SignIn> {
         self.validate()?;
+        ctx.rate_limit(rate_limit::SIGN_IN, Some(&self.email))?;
         let db = ctx.db.deref();
         if let Ok((id, uid, password, confirmed_at, locked_at)) = users::dsl::users
             .select((
@@ -211,6 +217,7 @@ impl SignInByEmail {
                             db,
                             &id,
                             &ctx.client_ip,
+                            &ctx.request_id,
                             &ctx.locale,
                             "nut.logs.user.sign-in"
                         )?;
@@ -257,6 +264,7 @@ impl ResetPassword {
                 db,
                 &id,
                 &ctx.client_ip,
+                &ctx.request_id,
                 &ctx.locale,
                 "nut.logs.user.change-password"
             )?;
@@ -287,7 +295,14 @@ impl Lock {
                     users::dsl::updated_at.eq(&now),
                 ))
                 .execute(db)?;
-            l!(db, &id, &ctx.client_ip, &ctx.locale, "nut.logs.user.lock")?;
+            l!(
+                db,
+                &id,
+                &ctx.client_ip,
+                &ctx.request_id,
+                &ctx.locale,
+                "nut.logs.user.lock"
+            )?;
             Ok(())
         })?;
         Ok(H::new())
@@ -321,7 +336,14 @@ impl Unlock {
                     users::dsl::updated_at.eq(&now),
                 ))
                 .execute(db)?;
-            l!(db, &id, &ctx.client_ip, &ctx.locale, "nut.logs.user.unlock")?;
+            l!(
+                db,
+                &id,
+                &ctx.client_ip,
+                &ctx.request_id,
+                &ctx.locale,
+                "nut.logs.user.unlock"
+            )?;
             Ok(())
         })?;
         Ok(H::new())
@@ -352,6 +374,7 @@ impl Confirm {
                 db,
                 &id,
                 &ctx.client_ip,
+                &ctx.request_id,
                 &ctx.locale,
                 "nut.logs.user.confirm"
             )?;
@@ -388,6 +411,7 @@ pub struct SignUp {
 impl SignUp {
     pub fn call(&self, ctx: &Context) -> Result<H> {
         self.validate()?;
+        ctx.rate_limit(rate_limit::SIGN_UP, Some(&self.email))?;
         let db = ctx.db.deref();
         let (_id, uid) = db.transaction::<_, Error, _>(|| {
             if dao::user::is_email_exist(db, &self.email)? {
@@ -398,21 +422,37 @@ impl SignUp {
                 db,
                 &id,
                 &ctx.client_ip,
+                &ctx.request_id,
                 &ctx.locale,
                 "nut.logs.user.sign-up"
             )?;
+            // the job row commits or rolls back together with the user
+            if ctx.app.producer.is_database() {
+                send_email(
+                    db,
+                    &ctx.home,
+                    &ctx.app.jwt,
+                    &ctx.app.producer,
+                    ACT_CONFIRM,
+                    &ctx.locale,
+                    &self.email,
+                    &uid,
+                )?;
+            }
             Ok((id, uid))
         })?;
-        send_email(
-            db,
-            &ctx.home,
-            &ctx.app.jwt,
-            &ctx.app.producer,
-            ACT_CONFIRM,
-            &ctx.locale,
-            &self.email,
-            &uid,
-        )?;
+        if !ctx.app.producer.is_database() {
+            send_email(
+                db,
+                &ctx.home,
+                &ctx.app.jwt,
+                &ctx.app.producer,
+                ACT_CONFIRM,
+                &ctx.locale,
+                &self.email,
+                &uid,
+            )?;
+        }
         Ok(H::new())
     }
 }
@@ -442,18 +482,18 @@ pub fn send_email(
         "home": home,
         "token": token,
     }));
-    queue::push(
-        producer,
-        consumers::send_mail::NAME,
-        1,
-        &consumers::send_mail::Mail {
-            to: email.clone(),
-            subject: i18n::tr(db, lang, &format!("{}.{}.subject", pre, act), &args)?,
-            body: i18n::tr(db, lang, &format!("{}.{}.body", pre, act), &args)?,
-            attachments: None,
-        },
-    )?;
-    Ok(())
+    let mail = consumers::send_mail::Mail {
+        to: email.clone(),
+        subject: i18n::tr(db, lang, &format!("{}.{}.subject", pre, act), &args)?,
+        body: i18n::tr(db, lang, &format!("{}.{}.body", pre, act), &args)?,
+        attachments: None,
+    };
+    // by the caller's connection, so it joins any open transaction
+    if producer.is_database() {
+        queue::enqueue(db, consumers::send_mail::NAME, 1, &mail)?;
+        return Ok(());
+    }
+    queue::push(producer, consumers::send_mail::NAME, 1, &mail)
 }
 
 // https://developers.line.me/en/docs/line-login/web/integrate-line-login/
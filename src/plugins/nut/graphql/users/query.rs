@@ -8,6 +8,7 @@ use super::super::super::super::super::{
     errors::Result,
     graphql::{context::Context, H},
     orm::schema::*,
+    rate_limit,
     rfc::UtcDateTime,
 };
 use super::super::super::models;
@@ -165,6 +166,7 @@ pub struct ForgotPassword {
 impl ForgotPassword {
     pub fn call(&self, ctx: &Context) -> Result<H> {
         self.validate()?;
+        ctx.rate_limit(rate_limit::FORGOT_PASSWORD, Some(&self.email))?;
         let db = ctx.db.deref();
         let uid = users::dsl::users
             .select(users::dsl::uid)
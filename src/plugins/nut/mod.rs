@@ -4,25 +4,66 @@ pub mod dao;
 pub mod graphql;
 pub mod models;
 
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
+use diesel::{dsl::max, prelude::*};
 use rocket::Route;
 use sitemap::structs::ChangeFreq;
 
-use super::super::{RssItem, SitemapItem};
+use super::super::{
+    errors::Result,
+    orm::{
+        schema::{cards, friend_links, links},
+        Connection as Db,
+    },
+    seo::Section,
+    RobotsItem, RssItem, SitemapItem,
+};
 
 pub fn routes() -> Vec<(&'static str, Vec<Route>)> {
-    vec![("/", routes![controllers::home])]
+    vec![("/", routes![controllers::home, controllers::upload])]
 }
 
-pub fn sitemap() -> Vec<SitemapItem> {
+pub fn robots() -> Vec<RobotsItem> {
     vec![(
+        "*".to_string(),
+        vec![],
+        vec!["/my/".to_string(), "/graphql".to_string()],
+    )]
+}
+
+pub fn sitemap() -> Vec<Section> {
+    vec![Section {
+        count: |_| Ok(1),
+        load: home,
+    }]
+}
+
+fn home(db: &Db, _offset: i64, _limit: i64) -> Result<Vec<SitemapItem>> {
+    // the home page is built from cards, links and friend links
+    let last = vec![
+        cards::dsl::cards
+            .select(max(cards::dsl::updated_at))
+            .first::<Option<NaiveDateTime>>(db)?,
+        links::dsl::links
+            .select(max(links::dsl::updated_at))
+            .first::<Option<NaiveDateTime>>(db)?,
+        friend_links::dsl::friend_links
+            .select(max(friend_links::dsl::updated_at))
+            .first::<Option<NaiveDateTime>>(db)?,
+    ].into_iter()
+        .filter_map(|x| x)
+        .max();
+    Ok(vec![(
         String::from("/"),
-        0.1,
+        1.0,
         ChangeFreq::Daily,
-        Utc::now().naive_utc(),
-    )]
+        match last {
+            Some(v) => v,
+            None => Utc::now().naive_utc(),
+        },
+    )])
 }
 
-pub fn rss(_lang: &String) -> Vec<RssItem> {
-    vec![]
+pub fn rss(_db: &Db, _lang: &String) -> Result<Vec<RssItem>> {
+    Ok(Vec::new())
 }
@@ -1,13 +1,30 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::ops::Deref;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration as StdDuration, SystemTime, UNIX_EPOCH};
 
 use amqp::{self, Basic};
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::{
+    connection::SimpleConnection,
+    delete, insert_into, prelude::*, sql_query,
+    sql_types::{BigInt, Binary, Nullable, SmallInt, Text, Timestamp, Varchar},
+    update,
+};
 use log;
 use mime;
 use serde::ser::Serialize;
-use serde_json;
+use serde_json::{self, Value};
 use uuid::Uuid;
 
-use super::{context::Context, errors::Result};
+use super::{
+    context::Context,
+    errors::{Error, Result},
+    metrics::{METRICS, QUEUE_PUBLISHED},
+    orm::{last_insert_id, schema::jobs, Connection as Db, Pool},
+};
 
 pub const BAD_PROVIDER: &'static str = "bad messing queue provider";
 
@@ -26,10 +43,264 @@ pub fn push<T: Serialize>(
     )
 }
 
+// enqueue a job by the given connection, so it could share the caller's transaction
+pub fn enqueue<T: Serialize>(
+    db: &Db,
+    type_: &'static str,
+    priority: u8,
+    payload: &T,
+) -> Result<i64> {
+    let id = insert(
+        db,
+        &type_.to_string(),
+        &format!("{}", mime::APPLICATION_JSON),
+        priority,
+        serde_json::to_vec(payload)?.as_slice(),
+    )?;
+    METRICS.inc(QUEUE_PUBLISHED, &[("type", type_)]);
+    Ok(id)
+}
+
+fn insert(
+    db: &Db,
+    type_: &String,
+    content_type: &String,
+    priority: u8,
+    payload: &[u8],
+) -> Result<i64> {
+    let now = Utc::now().naive_utc();
+    insert_into(jobs::dsl::jobs)
+        .values((
+            jobs::dsl::type_.eq(type_),
+            jobs::dsl::content_type.eq(content_type),
+            jobs::dsl::priority.eq(&(priority as i16)),
+            jobs::dsl::payload.eq(payload),
+            jobs::dsl::attempts.eq(&0),
+            jobs::dsl::run_at.eq(&now),
+            jobs::dsl::updated_at.eq(&now),
+            jobs::dsl::created_at.eq(&now),
+        ))
+        .execute(db)?;
+    let id = last_insert_id(db)?;
+    Ok(id)
+}
+
+#[derive(QueryableByName, Debug)]
+pub struct Job {
+    #[sql_type = "BigInt"]
+    pub id: i64,
+    #[sql_type = "Varchar"]
+    pub type_: String,
+    #[sql_type = "Varchar"]
+    pub content_type: String,
+    #[sql_type = "SmallInt"]
+    pub priority: i16,
+    #[sql_type = "Binary"]
+    pub payload: Vec<u8>,
+    #[sql_type = "SmallInt"]
+    pub attempts: i16,
+    #[sql_type = "Nullable<Text>"]
+    pub last_error: Option<String>,
+}
+
+#[cfg(feature = "postgresql")]
+const FETCH_JOB: &'static str = "SELECT id, type AS type_, content_type, priority, payload, attempts, last_error FROM jobs WHERE failed_at IS NULL AND run_at <= $1 ORDER BY priority DESC, run_at ASC LIMIT 1 FOR UPDATE SKIP LOCKED";
+#[cfg(feature = "mysql")]
+const FETCH_JOB: &'static str = "SELECT id, type AS type_, content_type, priority, payload, attempts, last_error FROM jobs WHERE failed_at IS NULL AND run_at <= ? ORDER BY priority DESC, run_at ASC LIMIT 1 FOR UPDATE SKIP LOCKED";
+// sqlite has a single writer, the surrounding transaction is enough
+#[cfg(feature = "sqlite")]
+const FETCH_JOB: &'static str = "SELECT id, type AS type_, content_type, priority, payload, attempts, last_error FROM jobs WHERE failed_at IS NULL AND run_at <= ? ORDER BY priority DESC, run_at ASC LIMIT 1";
+
+// lock the next ready job, must be called in a transaction
+pub fn fetch(db: &Db) -> Result<Option<Job>> {
+    let now = Utc::now().naive_utc();
+    let mut items = sql_query(FETCH_JOB)
+        .bind::<Timestamp, _>(&now)
+        .load::<Job>(db)?;
+    Ok(items.pop())
+}
+
+pub fn done(db: &Db, id: i64) -> Result<()> {
+    delete(jobs::dsl::jobs.filter(jobs::dsl::id.eq(&id))).execute(db)?;
+    Ok(())
+}
+
+// schedule the next attempt, or move the job into dead letters when retries are exhausted
+pub fn fail(db: &Db, id: i64, attempts: i16, error: &String, retry: &Retry) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    let it = jobs::dsl::jobs.filter(jobs::dsl::id.eq(&id));
+    if retry.exhausted(attempts) {
+        update(it)
+            .set((
+                jobs::dsl::attempts.eq(&attempts),
+                jobs::dsl::last_error.eq(&Some(error)),
+                jobs::dsl::failed_at.eq(&Some(now)),
+                jobs::dsl::updated_at.eq(&now),
+            ))
+            .execute(db)?;
+    } else {
+        update(it)
+            .set((
+                jobs::dsl::attempts.eq(&attempts),
+                jobs::dsl::last_error.eq(&Some(error)),
+                jobs::dsl::run_at.eq(&(now + retry.delay(attempts))),
+                jobs::dsl::updated_at.eq(&now),
+            ))
+            .execute(db)?;
+    }
+    Ok(())
+}
+
+// record an exhausted or malformed message from other providers as a dead letter
+pub fn bury(
+    db: &Db,
+    type_: &String,
+    content_type: &String,
+    priority: u8,
+    payload: &[u8],
+    attempts: i16,
+    error: &String,
+) -> Result<i64> {
+    let now = Utc::now().naive_utc();
+    insert_into(jobs::dsl::jobs)
+        .values((
+            jobs::dsl::type_.eq(type_),
+            jobs::dsl::content_type.eq(content_type),
+            jobs::dsl::priority.eq(&(priority as i16)),
+            jobs::dsl::payload.eq(payload),
+            jobs::dsl::attempts.eq(&attempts),
+            jobs::dsl::run_at.eq(&now),
+            jobs::dsl::last_error.eq(&Some(error)),
+            jobs::dsl::failed_at.eq(&Some(now)),
+            jobs::dsl::updated_at.eq(&now),
+            jobs::dsl::created_at.eq(&now),
+        ))
+        .execute(db)?;
+    let id = last_insert_id(db)?;
+    Ok(id)
+}
+
+pub fn dead_letters(
+    db: &Db,
+) -> Result<Vec<(i64, String, i16, Option<String>, Option<NaiveDateTime>)>> {
+    Ok(jobs::dsl::jobs
+        .select((
+            jobs::dsl::id,
+            jobs::dsl::type_,
+            jobs::dsl::attempts,
+            jobs::dsl::last_error,
+            jobs::dsl::failed_at,
+        ))
+        .filter(jobs::dsl::failed_at.is_not_null())
+        .order(jobs::dsl::failed_at.desc())
+        .load(db)?)
+}
+
+// replay a dead letter through the producer
+pub fn retry(db: &Db, producer: &Producer, id: i64) -> Result<()> {
+    let (type_, content_type, priority, payload) = jobs::dsl::jobs
+        .select((
+            jobs::dsl::type_,
+            jobs::dsl::content_type,
+            jobs::dsl::priority,
+            jobs::dsl::payload,
+        ))
+        .filter(jobs::dsl::id.eq(&id))
+        .filter(jobs::dsl::failed_at.is_not_null())
+        .first::<(String, String, i16, Vec<u8>)>(db)?;
+    let it = jobs::dsl::jobs.filter(jobs::dsl::id.eq(&id));
+    if let Some(_) = producer.db {
+        let now = Utc::now().naive_utc();
+        update(it)
+            .set((
+                jobs::dsl::attempts.eq(&0),
+                jobs::dsl::run_at.eq(&now),
+                jobs::dsl::failed_at.eq(&None::<NaiveDateTime>),
+                jobs::dsl::updated_at.eq(&now),
+            ))
+            .execute(db)?;
+        return Ok(());
+    }
+    // the row is kept unless the broker has taken the message
+    db.transaction::<_, Error, _>(|| {
+        delete(it).execute(db)?;
+        producer.push(
+            &Uuid::new_v4().to_string(),
+            &type_,
+            &content_type,
+            priority as u8,
+            payload.as_slice(),
+        )
+    })
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Config {
     pub name: String,
     pub rabbitmq: Option<RabbitMQ>,
+    pub database: Option<Database>,
+    pub memory: Option<Memory>,
+    pub retry: Option<Retry>,
+    pub schedules: Option<Vec<Schedule>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Schedule {
+    // unique name, also used as the lock key
+    pub name: String,
+    // sec min hour day-of-month month day-of-week [year]
+    pub cron: String,
+    // consumer's name
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub priority: u8,
+    pub payload: Option<Value>,
+}
+
+impl Config {
+    // the memory queue lives in one process, only used when nothing else is set
+    pub fn is_memory(&self) -> bool {
+        self.rabbitmq.is_none() && self.database.is_none() && self.memory.is_some()
+    }
+
+    pub fn retry(&self) -> Retry {
+        match self.retry {
+            Some(ref v) => v.clone(),
+            None => Retry {
+                max_attempts: 5,
+                backoff: 10,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Retry {
+    #[serde(rename = "maxattempts")]
+    pub max_attempts: i16,
+    // seconds to wait before the first retry, doubled after each failure
+    pub backoff: i64,
+}
+
+impl Retry {
+    pub fn exhausted(&self, attempts: i16) -> bool {
+        attempts >= self.max_attempts
+    }
+    pub fn delay(&self, attempts: i16) -> Duration {
+        Duration::seconds(self.backoff << (attempts.max(1) - 1).min(16))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Database {
+    // seconds to wait when there is no ready job
+    pub interval: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Memory {
+    // max pending tasks, 0 means unlimited
+    pub capacity: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -53,6 +324,20 @@ impl RabbitMQ {
             ..Default::default()
         }
     }
+    pub fn retry_queue(queue: &String) -> String {
+        format!("{}.retry", queue)
+    }
+
+    // only a tcp connect, a session would declare the queues on every probe
+    pub fn ping(&self) -> Result<()> {
+        for it in (&self.host[..], self.port).to_socket_addrs()? {
+            if TcpStream::connect_timeout(&it, StdDuration::from_secs(1)).is_ok() {
+                return Ok(());
+            }
+        }
+        Err(format!("can't connect to {}:{}", self.host, self.port).into())
+    }
+
     // fn url(&self) -> String {
     //     format!(
     //         "amqp://{}:{}@{}:{}/{}",
@@ -75,6 +360,25 @@ impl RabbitMQ {
             false, // nowait
             amqp::Table::new(),
         )?;
+        // expired messages in the retry queue go back to the main queue
+        let mut args = amqp::Table::new();
+        args.insert(
+            "x-dead-letter-exchange".to_string(),
+            amqp::TableEntry::LongString("".to_string()),
+        );
+        args.insert(
+            "x-dead-letter-routing-key".to_string(),
+            amqp::TableEntry::LongString(queue.clone()),
+        );
+        ch.queue_declare(
+            &RabbitMQ::retry_queue(&queue)[..],
+            false, // passive,
+            true,  // durable
+            false, // exclusive
+            false, // auto_delete
+            false, // nowait
+            args,
+        )?;
 
         f(&mut ch, &queue)?;
 
@@ -85,13 +389,243 @@ impl RabbitMQ {
     }
 }
 
+#[derive(Debug)]
+pub struct Task {
+    pub id: String,
+    pub type_: String,
+    pub content_type: String,
+    pub priority: u8,
+    pub payload: Vec<u8>,
+    pub attempts: i16,
+    seq: u64,
+    run_at: NaiveDateTime,
+}
+
+// higher priority first, then first in first out
+impl Ord for Task {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Task {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Task {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Task {}
+
+// waiting for a retry, the earliest run_at first
+struct Delayed(Task);
+
+impl Ord for Delayed {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .0
+            .run_at
+            .cmp(&self.0.run_at)
+            .then_with(|| other.0.seq.cmp(&self.0.seq))
+    }
+}
+
+impl PartialOrd for Delayed {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Delayed {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Delayed {}
+
+struct Channel {
+    capacity: usize,
+    seq: u64,
+    tasks: BinaryHeap<Task>,
+    delayed: BinaryHeap<Delayed>,
+}
+
+impl Channel {
+    // move the due tasks into the ready heap
+    fn schedule(&mut self, now: &NaiveDateTime) {
+        loop {
+            match self.delayed.peek() {
+                Some(it) if it.0.run_at <= *now => {}
+                _ => return,
+            }
+            if let Some(Delayed(it)) = self.delayed.pop() {
+                self.tasks.push(it);
+            }
+        }
+    }
+}
+
+// in-process priority channel shared by producer and worker threads
+struct Local {
+    channel: Mutex<Channel>,
+    ready: Condvar,
+}
+
+impl Local {
+    fn new(cfg: &Memory) -> Self {
+        Self {
+            channel: Mutex::new(Channel {
+                capacity: cfg.capacity,
+                seq: 0,
+                tasks: BinaryHeap::new(),
+                delayed: BinaryHeap::new(),
+            }),
+            ready: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<Channel>> {
+        match self.channel.lock() {
+            Ok(v) => Ok(v),
+            Err(e) => Err(format!("{}", e).into()),
+        }
+    }
+
+    fn push(
+        &self,
+        id: &String,
+        type_: &String,
+        content_type: &String,
+        priority: u8,
+        payload: &[u8],
+        attempts: i16,
+        delay: Duration,
+    ) -> Result<()> {
+        let mut ch = self.lock()?;
+        if ch.capacity > 0 && ch.tasks.len() + ch.delayed.len() >= ch.capacity {
+            return Err(format!("queue is full({})", ch.capacity).into());
+        }
+        ch.seq += 1;
+        let it = Task {
+            id: id.clone(),
+            type_: type_.clone(),
+            content_type: content_type.clone(),
+            priority: priority,
+            payload: payload.to_vec(),
+            attempts: attempts,
+            seq: ch.seq,
+            run_at: Utc::now().naive_utc() + delay,
+        };
+        if delay > Duration::zero() {
+            ch.delayed.push(Delayed(it));
+        } else {
+            ch.tasks.push(it);
+        }
+        self.ready.notify_one();
+        Ok(())
+    }
+
+    // blocks until the next task is due
+    fn pop(&self) -> Result<Task> {
+        let mut ch = self.lock()?;
+        loop {
+            let now = Utc::now().naive_utc();
+            ch.schedule(&now);
+            if let Some(it) = ch.tasks.pop() {
+                return Ok(it);
+            }
+            let timeout = ch
+                .delayed
+                .peek()
+                .and_then(|it| it.0.run_at.signed_duration_since(now).to_std().ok());
+            ch = match timeout {
+                Some(timeout) => match self.ready.wait_timeout(ch, timeout) {
+                    Ok((v, _)) => v,
+                    Err(e) => return Err(format!("{}", e).into()),
+                },
+                None => match self.ready.wait(ch) {
+                    Ok(v) => v,
+                    Err(e) => return Err(format!("{}", e).into()),
+                },
+            };
+        }
+    }
+}
+
 pub struct Producer {
     cfg: Config,
+    db: Option<Pool>,
+    local: Option<Local>,
 }
 
 impl Producer {
-    pub fn new(cfg: Config) -> Self {
-        Self { cfg: cfg }
+    pub fn new(cfg: Config, db: Pool) -> Self {
+        let mut it = Self {
+            cfg: cfg,
+            db: None,
+            local: None,
+        };
+        if let None = it.cfg.rabbitmq {
+            if let Some(_) = it.cfg.database {
+                it.db = Some(db);
+            } else if let Some(ref cfg) = it.cfg.memory {
+                it.local = Some(Local::new(cfg));
+            }
+        }
+        it
+    }
+
+    // jobs are rows of the jobs table, see enqueue
+    pub fn is_database(&self) -> bool {
+        self.db.is_some()
+    }
+
+    // check the connection of the broker
+    pub fn ping(&self) -> Result<()> {
+        if let Some(ref cfg) = self.cfg.rabbitmq {
+            return cfg.ping();
+        }
+        if let Some(ref pool) = self.db {
+            let db = pool.get()?;
+            db.deref().batch_execute("SELECT 1")?;
+            return Ok(());
+        }
+        if let Some(_) = self.local {
+            return Ok(());
+        }
+        Err(BAD_PROVIDER.into())
+    }
+
+    // blocks until a task is available, only for the in-process provider
+    pub fn pop(&self) -> Result<Task> {
+        if let Some(ref local) = self.local {
+            return local.pop();
+        }
+        Err(BAD_PROVIDER.into())
+    }
+
+    // put a failed task back to run after the delay, only for the in-process provider
+    pub fn requeue(&self, task: &Task, delay: Duration) -> Result<()> {
+        if let Some(ref local) = self.local {
+            return local.push(
+                &task.id,
+                &task.type_,
+                &task.content_type,
+                task.priority,
+                task.payload.as_slice(),
+                task.attempts,
+                delay,
+            );
+        }
+        Err(BAD_PROVIDER.into())
     }
 
     pub fn push(
@@ -103,8 +637,23 @@ impl Producer {
         payload: &[u8],
     ) -> Result<()> {
         log::info!("push task into queue {}@{}", id, self.cfg.name);
+        self.publish(id, type_, content_type, priority, payload)?;
+        METRICS.inc(QUEUE_PUBLISHED, &[("type", type_)]);
+        Ok(())
+    }
+
+    fn publish(
+        &self,
+        id: &String,
+        type_: &String,
+        content_type: &String,
+        priority: u8,
+        payload: &[u8],
+    ) -> Result<()> {
         if let Some(ref cfg) = self.cfg.rabbitmq {
             return cfg.open(self.cfg.name.clone(), move |ch, qu| {
+                // in a transaction, so that Ok means the broker has the message
+                ch.tx_select()?;
                 ch.basic_publish(
                     "",
                     &qu[..],
@@ -120,9 +669,19 @@ impl Producer {
                     },
                     payload.to_vec(),
                 )?;
+                ch.tx_commit()?;
                 Ok(())
             });
         }
+        if let Some(ref pool) = self.db {
+            let db = pool.get()?;
+            let db = db.deref();
+            insert(db, type_, content_type, priority, payload)?;
+            return Ok(());
+        }
+        if let Some(ref local) = self.local {
+            return local.push(id, type_, content_type, priority, payload, 0, Duration::zero());
+        }
         Err(BAD_PROVIDER.into())
     }
 }
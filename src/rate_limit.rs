@@ -0,0 +1,58 @@
+use chrono::Duration;
+use log;
+
+use super::{cache::Cache, errors::Result};
+
+pub const SIGN_IN: &'static str = "sign-in";
+pub const SIGN_UP: &'static str = "sign-up";
+pub const FORGOT_PASSWORD: &'static str = "forgot-password";
+pub const LEAVE_WORD: &'static str = "leave-word";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Key {
+    #[serde(rename = "ip")]
+    Ip,
+    #[serde(rename = "email")]
+    Email,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Rule {
+    // max hits in each window
+    pub limit: i64,
+    // seconds
+    pub window: i64,
+    pub by: Vec<Key>,
+}
+
+impl Rule {
+    // count a hit, return false if any of the keys is over the limit, cache errors fail the
+    // request instead of letting it through
+    pub fn hit(&self, cache: &Cache, action: &str, ip: &String, email: Option<&String>) -> Result<bool> {
+        let mut keys = Vec::new();
+        if self.by.contains(&Key::Ip) {
+            keys.push(format!("ip/{}", ip));
+        }
+        if self.by.contains(&Key::Email) {
+            // so that "Who@Example.com " shares the counter of "who@example.com"
+            if let Some(email) = email {
+                keys.push(format!("email/{}", email.trim().to_lowercase()));
+            }
+        }
+        let mut ok = true;
+        for k in keys {
+            if self.incr(cache, action, &k)? > self.limit {
+                log::warn!("too many requests {} from {}", action, k);
+                ok = false;
+            }
+        }
+        Ok(ok)
+    }
+
+    fn incr(&self, cache: &Cache, action: &str, key: &String) -> Result<i64> {
+        cache.incr(
+            &format!("rate-limit://{}/{}", action, key),
+            Duration::seconds(self.window),
+        )
+    }
+}
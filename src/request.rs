@@ -1,17 +1,22 @@
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::ops::Deref;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use diesel::prelude::*;
 use hyper::header::{AcceptLanguage, Authorization, Bearer, Header, Host, LanguageTag, Raw};
 use rocket::{
-    http::Status,
+    fairing::{self, Info, Kind},
+    http::{Header as HttpHeader, Status},
     request::{self, FromRequest},
-    Outcome, Request,
+    Data, Outcome, Request, Response, State,
 };
 use url::Url;
+use uuid::Uuid;
 
 use super::{
+    context::Context,
     errors::Result,
     orm::{schema::locales, PooledConnection as Db},
 };
@@ -31,6 +36,122 @@ impl<'a, 'r> FromRequest<'a, 'r> for Home {
     }
 }
 
+pub const X_REQUEST_ID: &'static str = "X-Request-Id";
+// logs.request_id is VARCHAR(36), the length of a uuid
+const REQUEST_ID_MAX_LEN: usize = 36;
+
+// propagated from the upstream proxy, or assigned by RequestIdFairing
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    pub fn parse(v: Option<&str>) -> Option<String> {
+        match v {
+            Some(v) if !v.is_empty()
+                && v.len() <= REQUEST_ID_MAX_LEN
+                && v.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+            {
+                Some(v.to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+// the id is picked once by RequestIdFairing, a new one here would differ on every call
+impl<'a, 'r> FromRequest<'a, 'r> for RequestId {
+    type Error = ();
+
+    fn from_request(req: &'a Request<'r>) -> request::Outcome<Self, ()> {
+        match Self::parse(req.headers().get_one(X_REQUEST_ID)) {
+            Some(v) => Outcome::Success(RequestId(v)),
+            None => Outcome::Failure((Status::InternalServerError, ())),
+        }
+    }
+}
+
+// rocket 0.3 has no request-local cache, so the id is kept in the request headers,
+// written before any guard runs and echoed in the response
+pub struct RequestIdFairing;
+
+impl fairing::Fairing for RequestIdFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request ID",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    fn on_request(&self, request: &mut Request, _: &Data) {
+        let id = match RequestId::parse(request.headers().get_one(X_REQUEST_ID)) {
+            Some(v) => v,
+            None => Uuid::new_v4().to_string(),
+        };
+        request.replace_header(HttpHeader::new(X_REQUEST_ID, id));
+    }
+
+    fn on_response(&self, request: &Request, response: &mut Response) {
+        if let Some(v) = request.headers().get_one(X_REQUEST_ID) {
+            response.set_raw_header(X_REQUEST_ID, v.to_string());
+        }
+    }
+}
+
+// the peer address, or the one forwarded by a trusted reverse proxy
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientIp(pub String);
+
+impl ClientIp {
+    fn parse(req: &Request, trusted: &Vec<IpAddr>) -> Option<IpAddr> {
+        let peer = req.remote()?.ip();
+        if !trusted.contains(&peer) {
+            return Some(peer);
+        }
+        if let Some(ip) = req
+            .headers()
+            .get_one("X-Real-IP")
+            .and_then(|v| v.trim().parse().ok())
+        {
+            return Some(ip);
+        }
+        // the right most address which is not one of our proxies
+        let forwarded = req
+            .headers()
+            .get("X-Forwarded-For")
+            .flat_map(|v| v.split(','))
+            .map(|v| v.trim().to_string())
+            .collect::<Vec<_>>();
+        let mut ip = peer;
+        for it in forwarded.iter().rev() {
+            match it.parse() {
+                Ok(v) => {
+                    ip = v;
+                    if !trusted.contains(&ip) {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        Some(ip)
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for ClientIp {
+    type Error = ();
+
+    fn from_request(req: &'a Request<'r>) -> request::Outcome<Self, ()> {
+        let trusted = match req.guard::<State<Arc<Context>>>() {
+            Outcome::Success(ctx) => ctx.config.http.trusted_proxies(),
+            _ => Vec::new(),
+        };
+        match Self::parse(req, &trusted) {
+            Some(v) => Outcome::Success(ClientIp(v.to_string())),
+            None => Outcome::Failure((Status::BadRequest, ())),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Token(pub Option<String>);
 
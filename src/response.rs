@@ -0,0 +1,174 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use chrono::NaiveDateTime;
+use hex;
+use md5::{self, Digest};
+use rocket::{
+    http::{ContentType, Header, Status},
+    response::{self, Responder},
+    Request, Response,
+};
+
+use super::{
+    compress::accept_gzip,
+    rfc::{parse_http_date, HttpDate},
+};
+
+pub fn etag(buf: &[u8]) -> String {
+    let mut h = md5::Md5::new();
+    h.input(buf);
+    format!("\"{}\"", hex::encode(h.result().as_slice()))
+}
+
+// https://developer.mozilla.org/en-US/docs/Web/HTTP/Conditional_requests
+pub struct Conditional<R> {
+    pub etag: String,
+    pub last_modified: Option<NaiveDateTime>,
+    pub body: R,
+}
+
+// true if the client copy is still valid
+fn fresh(req: &Request, etag: &String, last_modified: Option<NaiveDateTime>) -> bool {
+    // If-None-Match takes precedence over If-Modified-Since
+    if let Some(v) = req.headers().get_one("If-None-Match") {
+        return v.split(',').any(|it| {
+            let it = it.trim();
+            it == "*" || it.trim_left_matches("W/") == etag
+        });
+    }
+    if let (Some(v), Some(last)) = (req.headers().get_one("If-Modified-Since"), last_modified) {
+        if let Some(since) = parse_http_date(v) {
+            // http dates have no sub-second part
+            return last.timestamp() <= since.timestamp();
+        }
+    }
+    false
+}
+
+impl<'r, R: Responder<'r>> Responder<'r> for Conditional<R> {
+    fn respond_to(self, req: &Request) -> response::Result<'r> {
+        let mut res = if fresh(req, &self.etag, self.last_modified) {
+            Response::build().status(Status::NotModified).finalize()
+        } else {
+            self.body.respond_to(req)?
+        };
+        res.set_header(Header::new("ETag", self.etag));
+        if let Some(last) = self.last_modified {
+            res.set_header(Header::new("Last-Modified", last.to_http_date()));
+        }
+        Ok(res)
+    }
+}
+
+// files under a public folder, hidden ones are never served
+pub struct StaticFile {
+    path: PathBuf,
+    cache_control: Option<String>,
+    // by the file extension if not set
+    content_type: Option<ContentType>,
+    download: bool,
+}
+
+impl StaticFile {
+    pub fn open(root: PathBuf, file: PathBuf, cache_control: Option<String>) -> Option<Self> {
+        for it in file.components() {
+            match it {
+                Component::Normal(v) => {
+                    if v.to_str().map(|v| v.starts_with('.')).unwrap_or(true) {
+                        return None;
+                    }
+                }
+                _ => return None,
+            }
+        }
+        let path = root.join(file);
+        if !path.is_file() {
+            return None;
+        }
+        Some(Self {
+            path: path,
+            cache_control: cache_control,
+            content_type: None,
+            download: false,
+        })
+    }
+
+    // user uploads: sent with the type detected at upload time and never shown inline,
+    // so that nothing in them runs on our origin
+    pub fn download(mut self, content_type: ContentType) -> Self {
+        self.content_type = Some(content_type);
+        self.download = true;
+        self
+    }
+
+    // the precompressed sibling, eg. app.js.gz for app.js
+    fn gzipped(&self) -> Option<PathBuf> {
+        let mut name = self.path.file_name()?.to_os_string();
+        name.push(".gz");
+        let path = self.path.with_file_name(name);
+        if path.is_file() {
+            return Some(path);
+        }
+        None
+    }
+}
+
+fn modified(path: &Path) -> Option<(u64, NaiveDateTime)> {
+    let meta = fs::metadata(path).ok()?;
+    let it = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((
+        meta.len(),
+        NaiveDateTime::from_timestamp(it.as_secs() as i64, 0),
+    ))
+}
+
+impl<'r> Responder<'r> for StaticFile {
+    fn respond_to(self, req: &Request) -> response::Result<'r> {
+        let content_type = match self.content_type {
+            Some(ref v) => v.clone(),
+            None => self
+                .path
+                .extension()
+                .and_then(|v| v.to_str())
+                .and_then(ContentType::from_extension)
+                .unwrap_or(ContentType::Binary),
+        };
+        let gzipped = if accept_gzip(req) {
+            self.gzipped()
+        } else {
+            None
+        };
+        let path = match gzipped {
+            Some(ref v) => v,
+            None => &self.path,
+        };
+
+        let (size, last) = modified(path).ok_or(Status::NotFound)?;
+        // cheap validator, the content is not read for it
+        let etag = format!("\"{:x}-{:x}\"", size, last.timestamp());
+
+        let mut res = Response::build();
+        if fresh(req, &etag, Some(last)) {
+            res.status(Status::NotModified);
+        } else {
+            res.header(content_type)
+                .sized_body(fs::File::open(path).map_err(|_| Status::NotFound)?);
+            if gzipped.is_some() {
+                res.header(Header::new("Content-Encoding", "gzip"));
+            }
+        }
+        res.header(Header::new("ETag", etag))
+            .header(Header::new("Last-Modified", last.to_http_date()))
+            .header(Header::new("Vary", "Accept-Encoding"));
+        if let Some(v) = self.cache_control {
+            res.header(Header::new("Cache-Control", v));
+        }
+        if self.download {
+            res.header(Header::new("Content-Disposition", "attachment"))
+                .header(Header::new("X-Content-Type-Options", "nosniff"));
+        }
+        res.ok()
+    }
+}
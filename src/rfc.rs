@@ -47,3 +47,18 @@ impl UtcDate for NaiveDate {
         Date::<UTC>::from_utc(*self, *UTC::now().offset())
     }
 }
+
+// RFC 7231 IMF-fixdate, used by Last-Modified and If-Modified-Since
+pub trait HttpDate {
+    fn to_http_date(&self) -> String;
+}
+
+impl HttpDate for NaiveDateTime {
+    fn to_http_date(&self) -> String {
+        self.format("%a, %d %b %Y %T GMT").to_string()
+    }
+}
+
+pub fn parse_http_date(s: &str) -> Option<NaiveDateTime> {
+    DateTime::parse_from_rfc2822(s).ok().map(|v| v.naive_utc())
+}
@@ -1,26 +1,44 @@
 use std::io::Cursor;
 use std::ops::Deref;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::result::Result as StdResult;
 use std::sync::Arc;
+use std::time::Instant;
 
-use chrono::{DateTime, FixedOffset, Utc};
+use chrono::{DateTime, Utc};
+use diesel::{connection::SimpleConnection, prelude::*};
+use log;
 use robots_txt::Robots;
 use rocket::{
     http::{ContentType, Status},
-    response::{content::Xml, NamedFile, Responder},
+    response::{
+        content::{Content, Xml},
+        status, Responder,
+    },
     Catcher, Request, Response, Route, State,
 };
-use rss::{ChannelBuilder, ItemBuilder};
-use sitemap::{structs::UrlEntry, writer::SiteMapWriter, Error as SitemapError};
+use rocket_contrib::Json;
+use rss::{ChannelBuilder, GuidBuilder, ItemBuilder};
+use serde_json::Value;
 
 use super::{
+    compress,
     context::Context,
     errors::{Error, Result},
-    graphql,
-    orm::PooledConnection as Db,
-    plugins::{forum, nut},
+    graphql, i18n,
+    metrics::{self as prometheus, DB_CONNECTIONS, DB_IDLE_CONNECTIONS, DB_MAX_CONNECTIONS},
+    orm::{schema::attachments, PooledConnection as Db},
+    plugins::{
+        forum,
+        nut::{
+            self,
+            graphql::site::models::{RobotsRule, ROBOTS},
+        },
+    },
     request::Home,
+    response::{etag, Conditional, StaticFile},
+    seo, settings, theme, RssItem,
 };
 
 impl<'r> Responder<'r> for Error {
@@ -46,8 +64,17 @@ pub fn routes() -> Vec<(&'static str, Vec<Route>)> {
             global,
             assets,
             robots_txt,
+            site_verification,
+            sitemap_xml,
             sitemap_xml_gz,
-            rss_atom
+            sitemap_page,
+            rss_feed,
+            tag_rss_feed,
+            atom_feed,
+            tag_atom_feed,
+            healthz,
+            readyz,
+            metrics
         ],
     ));
     items
@@ -58,135 +85,393 @@ pub fn catchers() -> Vec<Catcher> {
 }
 
 #[get("/global/<file..>")]
-fn global(file: PathBuf) -> Result<NamedFile> {
-    Ok(NamedFile::open(
-        Path::new("themes").join("global").join(file),
-    )?)
+fn global(file: PathBuf, ctx: State<Arc<Context>>) -> Option<StaticFile> {
+    StaticFile::open(
+        Path::new(theme::ROOT).join("global"),
+        file,
+        ctx.config.http.cache_control("global"),
+    )
 }
 
 #[get("/assets/<file..>")]
-fn assets(file: PathBuf, ctx: State<Arc<Context>>) -> Result<NamedFile> {
-    Ok(NamedFile::open(
-        Path::new("themes")
-            .join(ctx.config.http.theme.clone())
-            .join("assets")
-            .join(file),
-    )?)
+fn assets(file: PathBuf, db: Db, ctx: State<Arc<Context>>) -> Option<StaticFile> {
+    StaticFile::open(
+        theme::assets(db.deref(), &ctx),
+        file,
+        ctx.config.http.cache_control("assets"),
+    )
 }
 
 #[get("/3rd/<file..>")]
-fn third(file: PathBuf) -> Result<NamedFile> {
-    Ok(NamedFile::open(Path::new("node_modules").join(file))?)
+fn third(file: PathBuf, ctx: State<Arc<Context>>) -> Option<StaticFile> {
+    StaticFile::open(
+        Path::new("node_modules").to_path_buf(),
+        file,
+        ctx.config.http.cache_control("3rd"),
+    )
 }
 
+// only the files recorded as attachments, with the type found at upload time
 #[get("/upload/<file..>")]
-fn upload(file: PathBuf) -> Result<NamedFile> {
-    Ok(NamedFile::open(Path::new("tmp").join("upload").join(file))?)
+fn upload(file: PathBuf, db: Db, ctx: State<Arc<Context>>) -> Option<StaticFile> {
+    let mime_type = attachments::dsl::attachments
+        .select(attachments::dsl::mime_type)
+        .filter(attachments::dsl::url.eq(file.to_str()?))
+        .first::<String>(db.deref())
+        .ok()?;
+    Some(
+        StaticFile::open(
+            ctx.storage.local(&PathBuf::new()).ok()?,
+            file,
+            ctx.config.http.cache_control("upload"),
+        )?.download(mime_type.parse::<ContentType>().unwrap_or(ContentType::Binary)),
+    )
+}
+
+fn robots_rules(db: &Db, ctx: &Context) -> BTreeMap<String, (Vec<String>, Vec<String>)> {
+    let mut items = Vec::new();
+    items.extend(nut::robots());
+    items.extend(forum::robots());
+    if let Ok(rules) = settings::get::<_, Vec<RobotsRule>>(db, &ctx.encryptor, &ROBOTS.to_string()) {
+        for it in rules {
+            items.push((it.user_agent, it.allow, it.disallow));
+        }
+    }
+    // merge the sections by user agent, "*" always exists for the sitemap
+    let mut rules = BTreeMap::new();
+    rules.insert("*".to_string(), (Vec::new(), Vec::new()));
+    for (agent, allow, disallow) in items {
+        let it = rules.entry(agent).or_insert((Vec::new(), Vec::new()));
+        for v in allow {
+            if !it.0.contains(&v) {
+                it.0.push(v);
+            }
+        }
+        for v in disallow {
+            if !it.1.contains(&v) {
+                it.1.push(v);
+            }
+        }
+    }
+    rules
 }
 
 // https://en.wikipedia.org/wiki/Robots_exclusion_standard
 #[get("/robots.txt")]
-fn robots_txt(home: Home) -> Result<String> {
+fn robots_txt(db: Db, home: Home, ctx: State<Arc<Context>>) -> Result<String> {
     let Home(home) = home;
-    Ok(format!(
-        "{}",
-        Robots::start_build()
-            .host(home.clone())
-            .start_section_for("*")
-            .disallow("/my/")
-            .sitemap((home + "/sitemap.xml").parse()?)
-            .end_section()
-            .finalize()
-    ))
+    let rules = robots_rules(db.deref(), &ctx);
+    let sitemap = format!("{}/sitemap.xml", home).parse()?;
+    let mut robots = Robots::start_build().host(home.clone());
+    for (agent, (allow, disallow)) in rules.iter() {
+        let mut section = robots.start_section_for(agent.as_str());
+        for v in allow {
+            section = section.allow(v.as_str());
+        }
+        for v in disallow {
+            section = section.disallow(v.as_str());
+        }
+        if agent == "*" {
+            section = section.sitemap(sitemap.clone());
+        }
+        robots = section.end_section();
+    }
+    Ok(format!("{}", robots.finalize()))
 }
 
-fn build_sitemap(home: &String, buf: &mut Vec<u8>) -> StdResult<(), SitemapError> {
-    let srt = SiteMapWriter::new(buf);
-    let mut urt = srt.start_urlset()?;
-    let fix = FixedOffset::east(0);
-
-    for items in vec![nut::sitemap(), forum::sitemap()] {
-        for (loc, priority, changefreq, lastmod) in items {
-            urt.url(
-                UrlEntry::builder()
-                    .loc(format!("{}{}", home, loc))
-                    .priority(priority)
-                    .changefreq(changefreq)
-                    .lastmod(DateTime::<Utc>::from_utc(lastmod, Utc).with_timezone(&fix)),
-            )?;
+// https://support.google.com/webmasters/answer/9008080
+// https://ziyuan.baidu.com/site/siteadd
+#[get("/<file>", rank = 10)]
+fn site_verification(file: String, db: Db, ctx: State<Arc<Context>>) -> Option<String> {
+    let db = db.deref();
+    let code = |key: &str| -> Option<String> {
+        settings::get::<_, String>(db, &ctx.encryptor, &key.to_string())
+            .ok()
+            .filter(|v| !v.is_empty())
+    };
+    if file.starts_with("google") && file.ends_with(".html") {
+        if let Some(code) = code("site.seo.google") {
+            if file == format!("google{}.html", code) {
+                return Some(format!("google-site-verification: {}", file));
+            }
         }
     }
+    if file.starts_with("baidu_verify_") && file.ends_with(".html") {
+        if let Some(code) = code("site.seo.baidu") {
+            if file == format!("baidu_verify_{}.html", code) {
+                return Some(code);
+            }
+        }
+    }
+    None
+}
 
-    urt.end()?;
-    Ok(())
+fn sitemap_sections() -> Vec<seo::Section> {
+    let mut items = Vec::new();
+    items.extend(nut::sitemap());
+    items.extend(forum::sitemap());
+    items
+}
+
+fn build_sitemap_index(db: &Db, home: &String) -> Result<String> {
+    let mut total = 0;
+    for it in sitemap_sections() {
+        total += (it.count)(db)?;
+    }
+    seo::sitemap_index(home, seo::pages(total))
 }
 
 // https://en.wikipedia.org/wiki/Site_map
 // https://www.sitemaps.org/protocol.html
 #[get("/sitemap.xml")]
-fn sitemap_xml_gz(home: Home) -> Result<Xml<Vec<u8>>> {
+fn sitemap_xml(db: Db, home: Home) -> Result<Xml<String>> {
     let Home(home) = home;
-    let mut buf = Vec::new();
-    match build_sitemap(&home, &mut buf) {
-        Ok(_) => Ok(Xml(buf)),
-        Err(e) => Err(format!("{:?}", e).into()),
+    Ok(Xml(build_sitemap_index(db.deref(), &home)?))
+}
+
+#[get("/sitemap.xml.gz")]
+fn sitemap_xml_gz(db: Db, home: Home) -> Result<Content<Vec<u8>>> {
+    let Home(home) = home;
+    let buf = build_sitemap_index(db.deref(), &home)?;
+    Ok(Content(
+        ContentType::new("application", "gzip"),
+        compress::gzip(buf.as_bytes())?,
+    ))
+}
+
+// split by the 50,000 urls limit, page starts from 1
+#[get("/sitemaps/<page>")]
+fn sitemap_page(db: Db, home: Home, page: i64) -> Result<Option<Xml<String>>> {
+    let Home(home) = home;
+    let db = db.deref();
+    if page <= 0 {
+        return Ok(None);
     }
+    let items = seo::page(db, &sitemap_sections(), page)?;
+    if items.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Xml(seo::sitemap(
+        &home,
+        &i18n::languages(db)?,
+        &items,
+    )?)))
 }
-// fn sitemap_xml_gz<'r>() -> response::Result<'r> {
-//     let mut buf = Cursor::new(Vec::new());
-//     match build_sitemap(&mut buf) {
-//         Ok(_) => Response::build()
-//             .sized_body(buf)
-//             .header(ContentType::XML)
-//             .header(ContentType::new("application", "x-gzip"))
-//             .header(Header::from(ContentEncoding(vec![Encoding::Gzip])))
-//             .header(Header::from(ContentDisposition {
-//                 disposition: DispositionType::Attachment,
-//                 parameters: vec![DispositionParam::Filename(
-//                     Charset::Us_Ascii,
-//                     None,
-//                     b"sitemap.xml.gz".to_vec(),
-//                 )],
-//             }))
-//             .ok(),
-//         Err(e) => {
-//             log::error!("{:?}", e);
-//             Err(Status::InternalServerError)
-//         }
-//     }
-// }
 
+fn feed_items(db: &Db, lang: &String) -> Result<Vec<RssItem>> {
+    let mut items = Vec::new();
+    items.extend(nut::rss(db, lang)?);
+    items.extend(forum::rss(db, lang)?);
+    items.sort_by(|a, b| b.3.cmp(&a.3));
+    items.truncate(seo::FEED_MAX_ITEMS);
+    Ok(items)
+}
+
+fn build_rss(
+    home: &String,
+    lang: &String,
+    title: String,
+    description: String,
+    copyright: String,
+    items: Vec<RssItem>,
+) -> Result<Conditional<Content<Vec<u8>>>> {
+    let last = seo::feed_updated(&items);
+    let mut fields = Vec::new();
+    for (url, title, desc, last) in items {
+        let link = format!("{}{}", home, url);
+        fields.push(
+            ItemBuilder::default()
+                .link(link.clone())
+                .guid(GuidBuilder::default().value(link).permalink(true).build()?)
+                .title(title)
+                .description(desc)
+                .pub_date(DateTime::<Utc>::from_utc(last, Utc).to_rfc2822())
+                .build()?,
+        );
+    }
+    let mut ch = ChannelBuilder::default();
+    ch.link(home.clone())
+        .language(lang.clone())
+        .title(title)
+        .description(description)
+        .copyright(copyright)
+        .items(fields);
+    if let Some(last) = last {
+        let last = DateTime::<Utc>::from_utc(last, Utc).to_rfc2822();
+        ch.pub_date(last.clone()).last_build_date(last);
+    }
+    let mut buf = Vec::new();
+    ch.build()?.write_to(&mut buf)?;
+
+    Ok(Conditional {
+        etag: etag(&buf),
+        last_modified: last,
+        body: Content(ContentType::new("application", "rss+xml"), buf),
+    })
+}
+
+fn build_atom(
+    home: &String,
+    href: String,
+    lang: &String,
+    title: String,
+    subtitle: String,
+    items: Vec<RssItem>,
+) -> Result<Conditional<Content<String>>> {
+    let buf = seo::atom(home, &href, lang, &title, &subtitle, &items)?;
+    Ok(Conditional {
+        etag: etag(buf.as_bytes()),
+        last_modified: seo::feed_updated(&items),
+        body: Content(ContentType::new("application", "atom+xml"), buf),
+    })
+}
+
+// https://validator.w3.org/feed/docs/rss2.html
 #[get("/rss/<lang>")]
-fn rss_atom(db: Db, home: Home, lang: String) -> Result<Xml<Vec<u8>>> {
+fn rss_feed(db: Db, home: Home, lang: String) -> Result<Conditional<Content<Vec<u8>>>> {
     let Home(home) = home;
     let db = db.deref();
-    let mut fields = Vec::new();
-    for items in vec![nut::rss(&lang), forum::rss(&lang)] {
-        for (url, title, desc, last) in items {
-            fields.push(
-                ItemBuilder::default()
-                    .link(format!("{}{}", home, url))
-                    .title(title)
-                    .description(desc)
-                    .pub_date(DateTime::<Utc>::from_utc(last, Utc).to_rfc3339())
-                    .build()?,
-            );
+    build_rss(
+        &home,
+        &lang,
+        t!(db, &lang, "site.title"),
+        t!(db, &lang, "site.description"),
+        t!(db, &lang, "site.copyright"),
+        feed_items(db, &lang)?,
+    )
+}
+
+#[get("/rss/<lang>/<tag>")]
+fn tag_rss_feed(
+    db: Db,
+    home: Home,
+    lang: String,
+    tag: i64,
+) -> Result<Option<Conditional<Content<Vec<u8>>>>> {
+    let Home(home) = home;
+    let db = db.deref();
+    match forum::tag_rss(db, &lang, tag)? {
+        Some((name, items)) => Ok(Some(build_rss(
+            &home,
+            &lang,
+            format!("{} - {}", t!(db, &lang, "site.title"), name),
+            t!(db, &lang, "site.description"),
+            t!(db, &lang, "site.copyright"),
+            items,
+        )?)),
+        None => Ok(None),
+    }
+}
+
+// https://tools.ietf.org/html/rfc4287
+#[get("/atom/<lang>")]
+fn atom_feed(db: Db, home: Home, lang: String) -> Result<Conditional<Content<String>>> {
+    let Home(home) = home;
+    let db = db.deref();
+    build_atom(
+        &home,
+        format!("{}/atom/{}", home, lang),
+        &lang,
+        t!(db, &lang, "site.title"),
+        t!(db, &lang, "site.description"),
+        feed_items(db, &lang)?,
+    )
+}
+
+#[get("/atom/<lang>/<tag>")]
+fn tag_atom_feed(
+    db: Db,
+    home: Home,
+    lang: String,
+    tag: i64,
+) -> Result<Option<Conditional<Content<String>>>> {
+    let Home(home) = home;
+    let db = db.deref();
+    match forum::tag_rss(db, &lang, tag)? {
+        Some((name, items)) => Ok(Some(build_atom(
+            &home,
+            format!("{}/atom/{}/{}", home, lang, tag),
+            &lang,
+            format!("{} - {}", t!(db, &lang, "site.title"), name),
+            t!(db, &lang, "site.description"),
+            items,
+        )?)),
+        None => Ok(None),
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct Probe {
+    name: &'static str,
+    up: bool,
+    // milliseconds
+    latency: f64,
+}
+
+impl Probe {
+    fn new<F: Fn() -> Result<()>>(name: &'static str, f: F) -> Self {
+        let now = Instant::now();
+        let ret = f();
+        let d = now.elapsed();
+        // the endpoint is public, keep the details in the log
+        if let Err(ref e) = ret {
+            log::error!("probe {} {:?}", name, e);
+        }
+        Self {
+            name: name,
+            up: ret.is_ok(),
+            latency: d.as_secs() as f64 * 1e3 + d.subsec_nanos() as f64 / 1e6,
         }
     }
-    let mut buf = Vec::new();
-    let ch = ChannelBuilder::default()
-        .link(home.clone())
-        .language(lang.clone())
-        .pub_date(Utc::now().to_rfc3339())
-        .title(t!(db, &lang, "site.title"))
-        .description(t!(db, &lang, "site.description"))
-        .copyright(t!(db, &lang, "site.copyright"))
-        .items(fields)
-        .build()?;
+}
 
-    ch.write_to(&mut buf)?;
+#[derive(Serialize, Debug)]
+struct Readiness {
+    ok: bool,
+    checks: Vec<Probe>,
+}
+
+// liveness, the process is up and serving
+#[get("/healthz")]
+fn healthz() -> Json<Value> {
+    Json(json!({ "ok": true }))
+}
+
+// readiness, all the dependencies are reachable
+#[get("/readyz")]
+fn readyz(ctx: State<Arc<Context>>) -> status::Custom<Json<Readiness>> {
+    let checks = vec![
+        Probe::new("database", || {
+            let db = ctx.db.get()?;
+            db.deref().batch_execute("SELECT 1")?;
+            Ok(())
+        }),
+        Probe::new("cache", || ctx.cache.ping()),
+        Probe::new("queue", || ctx.producer.ping()),
+    ];
+    let ok = checks.iter().all(|it| it.up);
+    status::Custom(
+        if ok {
+            Status::Ok
+        } else {
+            Status::ServiceUnavailable
+        },
+        Json(Readiness {
+            ok: ok,
+            checks: checks,
+        }),
+    )
+}
 
-    Ok(Xml(buf))
+// https://prometheus.io/docs/instrumenting/exposition_formats/
+#[get("/metrics")]
+fn metrics(ctx: State<Arc<Context>>) -> String {
+    let state = ctx.db.state();
+    prometheus::METRICS.render(&[
+        (DB_CONNECTIONS, state.connections as f64),
+        (DB_IDLE_CONNECTIONS, state.idle_connections as f64),
+        (DB_MAX_CONNECTIONS, ctx.db.max_size() as f64),
+    ])
 }
 
 #[catch(404)]
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use rocket::{
+    fairing::{self, Info, Kind},
+    http::Header,
+    Request, Response,
+};
+use rocket_cors::{self, AllowedHeaders, AllowedOrigins, Method};
+
+use super::errors::Result;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Cors {
+    // empty for any origin, not allowed together with credentials
+    pub origins: Vec<String>,
+    // GET, POST, PATCH, DELETE...
+    pub methods: Vec<String>,
+    // empty for any header
+    pub headers: Vec<String>,
+    pub credentials: bool,
+}
+
+impl Cors {
+    pub fn fairing(&self) -> Result<rocket_cors::Cors> {
+        let origins = if self.origins.is_empty() {
+            // any site could read the responses of a signed in user
+            if self.credentials {
+                return Err("cors credentials need explicit origins".into());
+            }
+            AllowedOrigins::all()
+        } else {
+            let items: Vec<&str> = self.origins.iter().map(|x| x.as_str()).collect();
+            let (origins, failed) = AllowedOrigins::some(&items);
+            if !failed.is_empty() {
+                return Err(format!(
+                    "bad cors origins {:?}",
+                    failed.keys().collect::<Vec<_>>()
+                ).into());
+            }
+            origins
+        };
+
+        let mut methods = HashSet::new();
+        for it in self.methods.iter() {
+            match it.to_uppercase().parse::<Method>() {
+                Ok(v) => {
+                    methods.insert(v);
+                }
+                Err(_) => return Err(format!("bad cors method {}", it).into()),
+            };
+        }
+
+        let headers = if self.headers.is_empty() {
+            AllowedHeaders::all()
+        } else {
+            let items: Vec<&str> = self.headers.iter().map(|x| x.as_str()).collect();
+            AllowedHeaders::some(&items)
+        };
+
+        Ok(rocket_cors::Cors {
+            allowed_origins: origins,
+            allowed_methods: methods,
+            allowed_headers: headers,
+            allow_credentials: self.credentials,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Headers {
+    // Content-Security-Policy
+    pub csp: Option<String>,
+    // Strict-Transport-Security max-age in seconds, only enable it behind https
+    pub hsts: Option<u64>,
+    // every subdomain must serve https too before turning it on
+    #[serde(rename = "hstsincludesubdomains")]
+    pub hsts_include_subdomains: Option<bool>,
+    // X-Frame-Options: DENY or SAMEORIGIN
+    #[serde(rename = "frameoptions")]
+    pub frame_options: Option<String>,
+    // Referrer-Policy
+    #[serde(rename = "referrerpolicy")]
+    pub referrer_policy: Option<String>,
+}
+
+pub struct Fairing {
+    headers: Vec<Header<'static>>,
+}
+
+impl Fairing {
+    pub fn new(cfg: &Headers) -> Self {
+        let mut headers = Vec::new();
+        if let Some(ref v) = cfg.csp {
+            headers.push(Header::new("Content-Security-Policy", v.clone()));
+        }
+        if let Some(v) = cfg.hsts {
+            headers.push(Header::new(
+                "Strict-Transport-Security",
+                match cfg.hsts_include_subdomains {
+                    Some(true) => format!("max-age={}; includeSubDomains", v),
+                    _ => format!("max-age={}", v),
+                },
+            ));
+        }
+        if let Some(ref v) = cfg.frame_options {
+            headers.push(Header::new("X-Frame-Options", v.clone()));
+        }
+        if let Some(ref v) = cfg.referrer_policy {
+            headers.push(Header::new("Referrer-Policy", v.clone()));
+        }
+        headers.push(Header::new("X-Content-Type-Options", "nosniff"));
+        Self { headers: headers }
+    }
+}
+
+impl fairing::Fairing for Fairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Security headers",
+            kind: Kind::Response,
+        }
+    }
+
+    fn on_response(&self, _: &Request, response: &mut Response) {
+        for it in self.headers.iter() {
+            // keep the ones set by the handler
+            if !response.headers().contains(it.name()) {
+                response.set_header(it.clone());
+            }
+        }
+    }
+}
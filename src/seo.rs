@@ -0,0 +1,176 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sitemap::structs::ChangeFreq;
+
+use super::{errors::Result, orm::Connection as Db, RssItem, SitemapItem};
+
+// https://www.sitemaps.org/protocol.html#index
+pub const SITEMAP_MAX_URLS: usize = 50_000;
+pub const FEED_MAX_ITEMS: usize = 20;
+
+const XML_HEADER: &'static str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+const SITEMAP_NS: &'static str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+const XHTML_NS: &'static str = "http://www.w3.org/1999/xhtml";
+const ATOM_NS: &'static str = "http://www.w3.org/2005/Atom";
+
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+pub fn w3c_datetime(t: &NaiveDateTime) -> String {
+    DateTime::<Utc>::from_utc(*t, Utc).to_rfc3339()
+}
+
+fn change_freq(v: &ChangeFreq) -> &'static str {
+    match *v {
+        ChangeFreq::Always => "always",
+        ChangeFreq::Hourly => "hourly",
+        ChangeFreq::Daily => "daily",
+        ChangeFreq::Weekly => "weekly",
+        ChangeFreq::Monthly => "monthly",
+        ChangeFreq::Yearly => "yearly",
+        ChangeFreq::Never => "never",
+    }
+}
+
+// the Locale guard picks the language from the "locale" argument
+pub fn localized(url: &String, lang: &String) -> String {
+    format!(
+        "{}{}locale={}",
+        url,
+        if url.contains('?') { '&' } else { '?' },
+        lang
+    )
+}
+
+// urls of one table in a stable order, so that a page could be loaded by offset and limit
+pub struct Section {
+    pub count: fn(&Db) -> Result<i64>,
+    pub load: fn(&Db, i64, i64) -> Result<Vec<SitemapItem>>,
+}
+
+pub fn pages(total: i64) -> i64 {
+    let size = SITEMAP_MAX_URLS as i64;
+    (total + size - 1) / size
+}
+
+// page number starts from 1
+pub fn page(db: &Db, sections: &[Section], page: i64) -> Result<Vec<SitemapItem>> {
+    let mut offset = (page - 1) * SITEMAP_MAX_URLS as i64;
+    let mut limit = SITEMAP_MAX_URLS as i64;
+    let mut items = Vec::new();
+    for it in sections {
+        if limit <= 0 {
+            break;
+        }
+        let count = (it.count)(db)?;
+        if offset >= count {
+            offset -= count;
+            continue;
+        }
+        let size = (count - offset).min(limit);
+        items.extend((it.load)(db, offset, size)?);
+        offset = 0;
+        limit -= size;
+    }
+    Ok(items)
+}
+
+// lastmod of each page is left out, it would need the rows of every page
+pub fn sitemap_index(home: &String, pages: i64) -> Result<String> {
+    let mut buf = String::new();
+    writeln!(buf, "{}", XML_HEADER)?;
+    writeln!(buf, r#"<sitemapindex xmlns="{}">"#, SITEMAP_NS)?;
+    for i in 0..pages {
+        writeln!(buf, "  <sitemap>")?;
+        writeln!(
+            buf,
+            "    <loc>{}</loc>",
+            escape(&format!("{}/sitemaps/{}", home, i + 1))
+        )?;
+        writeln!(buf, "  </sitemap>")?;
+    }
+    writeln!(buf, "</sitemapindex>")?;
+    Ok(buf)
+}
+
+// https://support.google.com/webmasters/answer/189077
+pub fn sitemap(home: &String, languages: &Vec<String>, items: &[SitemapItem]) -> Result<String> {
+    let mut buf = String::new();
+    writeln!(buf, "{}", XML_HEADER)?;
+    writeln!(
+        buf,
+        r#"<urlset xmlns="{}" xmlns:xhtml="{}">"#,
+        SITEMAP_NS, XHTML_NS
+    )?;
+    for (loc, priority, freq, lastmod) in items {
+        let loc = format!("{}{}", home, loc);
+        writeln!(buf, "  <url>")?;
+        writeln!(buf, "    <loc>{}</loc>", escape(&loc))?;
+        for lang in languages {
+            writeln!(
+                buf,
+                r#"    <xhtml:link rel="alternate" hreflang="{}" href="{}"/>"#,
+                escape(lang),
+                escape(&localized(&loc, lang))
+            )?;
+        }
+        writeln!(buf, "    <lastmod>{}</lastmod>", w3c_datetime(lastmod))?;
+        writeln!(buf, "    <changefreq>{}</changefreq>", change_freq(freq))?;
+        writeln!(buf, "    <priority>{:.1}</priority>", priority)?;
+        writeln!(buf, "  </url>")?;
+    }
+    writeln!(buf, "</urlset>")?;
+    Ok(buf)
+}
+
+// the newest item, so that unchanged feeds keep the same body
+pub fn feed_updated(items: &[RssItem]) -> Option<NaiveDateTime> {
+    items.iter().map(|(_, _, _, last)| *last).max()
+}
+
+// https://tools.ietf.org/html/rfc4287
+pub fn atom(
+    home: &String,
+    href: &String,
+    lang: &String,
+    title: &String,
+    subtitle: &String,
+    items: &[RssItem],
+) -> Result<String> {
+    let updated = match feed_updated(items) {
+        Some(v) => v,
+        None => NaiveDateTime::from_timestamp(0, 0),
+    };
+    let mut buf = String::new();
+    writeln!(buf, "{}", XML_HEADER)?;
+    writeln!(buf, r#"<feed xmlns="{}" xml:lang="{}">"#, ATOM_NS, escape(lang))?;
+    writeln!(buf, "  <id>{}</id>", escape(href))?;
+    writeln!(buf, "  <title>{}</title>", escape(title))?;
+    writeln!(buf, "  <subtitle>{}</subtitle>", escape(subtitle))?;
+    writeln!(buf, "  <updated>{}</updated>", w3c_datetime(&updated))?;
+    writeln!(buf, "  <author><name>{}</name></author>", escape(title))?;
+    writeln!(buf, r#"  <link rel="self" href="{}"/>"#, escape(href))?;
+    writeln!(
+        buf,
+        r#"  <link rel="alternate" type="text/html" href="{}"/>"#,
+        escape(home)
+    )?;
+    for (url, title, summary, last) in items {
+        let url = format!("{}{}", home, url);
+        writeln!(buf, "  <entry>")?;
+        writeln!(buf, "    <id>{}</id>", escape(&url))?;
+        writeln!(buf, "    <title>{}</title>", escape(title))?;
+        writeln!(buf, r#"    <link href="{}"/>"#, escape(&url))?;
+        writeln!(buf, "    <updated>{}</updated>", w3c_datetime(last))?;
+        writeln!(buf, "    <summary>{}</summary>", escape(summary))?;
+        writeln!(buf, "  </entry>")?;
+    }
+    writeln!(buf, "</feed>")?;
+    Ok(buf)
+}
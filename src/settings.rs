@@ -7,7 +7,7 @@ use serde_json;
 
 use super::{
     errors::Result,
-    orm::{schema::settings, Connection as Db},
+    orm::{last_insert_id, schema::settings, Connection as Db},
     utils::Encryptor,
 };
 
@@ -60,15 +60,17 @@ pub fn set<K: Serialize, V: Serialize>(
                 .execute(db)?;
             Ok(id)
         }
-        Err(_) => Ok(insert_into(settings::dsl::settings)
-            .values((
-                settings::dsl::key.eq(&key),
-                settings::dsl::value.eq(&val),
-                settings::dsl::salt.eq(&salt),
-                settings::dsl::updated_at.eq(&now),
-                settings::dsl::created_at.eq(&now),
-            ))
-            .returning(settings::dsl::id)
-            .get_result::<i64>(db)?),
+        Err(_) => {
+            insert_into(settings::dsl::settings)
+                .values((
+                    settings::dsl::key.eq(&key),
+                    settings::dsl::value.eq(&val),
+                    settings::dsl::salt.eq(&salt),
+                    settings::dsl::updated_at.eq(&now),
+                    settings::dsl::created_at.eq(&now),
+                ))
+                .execute(db)?;
+            Ok(last_insert_id(db)?)
+        }
     }
 }
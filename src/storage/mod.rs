@@ -0,0 +1,180 @@
+pub mod nfs;
+pub mod s3;
+
+use std::path::{Path, PathBuf};
+use std::str;
+
+use chrono::Utc;
+use mime;
+use uuid::Uuid;
+
+use super::{env::Aws, errors::Result};
+
+pub use self::nfs::Nfs;
+pub use self::s3::S3;
+
+pub const BAD_PROVIDER: &'static str = "bad storage provider";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Config {
+    pub nfs: Option<Nfs>,
+    pub s3: Option<S3>,
+}
+
+pub trait Provider: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn put(&self, key: &String, content_type: &String, body: &[u8]) -> Result<()>;
+    fn get(&self, key: &String) -> Result<Vec<u8>>;
+    fn delete(&self, key: &String) -> Result<()>;
+    fn exists(&self, key: &String) -> Result<bool>;
+    fn url(&self, key: &String) -> Result<String>;
+}
+
+// year/month/day/uuid.ext, the extension comes from the detected type rather than
+// the client's file name, so x.htm with a text body is stored as .txt
+pub fn key(content_type: &String) -> String {
+    let dir = Utc::now().format("%Y/%m/%d");
+    let id = Uuid::new_v4();
+    match extension(content_type) {
+        Some(ext) => format!("{}/{}.{}", dir, id, ext),
+        None => format!("{}/{}", dir, id),
+    }
+}
+
+fn extension(content_type: &String) -> Option<&'static str> {
+    for (_, _, v, ext) in MAGIC_NUMBERS {
+        if *v == content_type {
+            return Some(*ext);
+        }
+    }
+    for (ext, v) in EXTENSIONS {
+        if *v == content_type {
+            return Some(*ext);
+        }
+    }
+    None
+}
+
+const MAGIC_NUMBERS: &'static [(usize, &'static [u8], &'static str, &'static str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "image/png", "png"),
+    (0, b"\xff\xd8\xff", "image/jpeg", "jpg"),
+    (0, b"GIF87a", "image/gif", "gif"),
+    (0, b"GIF89a", "image/gif", "gif"),
+    (8, b"WEBP", "image/webp", "webp"),
+    (0, b"%PDF-", "application/pdf", "pdf"),
+    (0, b"PK\x03\x04", "application/zip", "zip"),
+    (0, b"\x1f\x8b", "application/gzip", "gz"),
+    (0, b"ID3", "audio/mpeg", "mp3"),
+    (0, b"OggS", "audio/ogg", "ogg"),
+    (4, b"ftyp", "video/mp4", "mp4"),
+];
+
+const EXTENSIONS: &'static [(&'static str, &'static str)] = &[
+    ("txt", "text/plain; charset=utf-8"),
+    ("md", "text/markdown; charset=utf-8"),
+    ("csv", "text/csv; charset=utf-8"),
+    ("html", "text/html; charset=utf-8"),
+    ("css", "text/css; charset=utf-8"),
+    ("js", "application/javascript"),
+    ("json", "application/json"),
+    ("xml", "application/xml"),
+    ("svg", "image/svg+xml"),
+];
+
+// browsers run scripts in these, so they must not be served from our origin
+const ACTIVE_TYPES: &'static [&'static str] = &[
+    "text/html",
+    "application/xhtml+xml",
+    "image/svg+xml",
+    "text/xml",
+    "application/xml",
+    "application/javascript",
+];
+
+pub fn is_active(content_type: &String) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_lowercase();
+    ACTIVE_TYPES.iter().any(|v| *v == essence)
+}
+
+// detect by magic numbers at first, then by the file extension
+pub fn content_type(name: &String, body: &[u8]) -> String {
+    for (offset, magic, v, _) in MAGIC_NUMBERS {
+        let end = offset + magic.len();
+        if body.len() >= end && &body[*offset..end] == *magic {
+            return v.to_string();
+        }
+    }
+    if let Some(ext) = Path::new(name).extension().and_then(|v| v.to_str()) {
+        let ext = ext.to_lowercase();
+        for (k, v) in EXTENSIONS {
+            if *k == ext {
+                return v.to_string();
+            }
+        }
+    }
+    if str::from_utf8(body).is_ok() {
+        return EXTENSIONS[0].1.to_string();
+    }
+    format!("{}", mime::APPLICATION_OCTET_STREAM)
+}
+
+pub struct Storage {
+    cfg: Config,
+    provider: Option<Box<Provider>>,
+}
+
+impl Storage {
+    pub fn new(cfg: Config, aws: &Aws) -> Result<Self> {
+        // s3 wins, the nfs section may stay for storage:migrate
+        let mut provider: Option<Box<Provider>> = None;
+        if let Some(ref v) = cfg.s3 {
+            provider = Some(Box::new(v.open(aws)?));
+        } else if let Some(ref v) = cfg.nfs {
+            provider = Some(Box::new(v.clone()));
+        }
+        Ok(Self {
+            cfg: cfg,
+            provider: provider,
+        })
+    }
+
+    fn provider(&self) -> Result<&Box<Provider>> {
+        match self.provider {
+            Some(ref v) => Ok(v),
+            None => Err(BAD_PROVIDER.into()),
+        }
+    }
+
+    // store the file under a new key and return it, keep the key rather than the url,
+    // s3 urls are presigned and expire
+    pub fn put(&self, content_type: &String, body: &[u8]) -> Result<String> {
+        let key = key(content_type);
+        self.provider()?.put(&key, content_type, body)?;
+        Ok(key)
+    }
+    pub fn get(&self, key: &String) -> Result<Vec<u8>> {
+        self.provider()?.get(key)
+    }
+    pub fn delete(&self, key: &String) -> Result<()> {
+        self.provider()?.delete(key)
+    }
+    pub fn exists(&self, key: &String) -> Result<bool> {
+        self.provider()?.exists(key)
+    }
+    pub fn url(&self, key: &String) -> Result<String> {
+        self.provider()?.url(key)
+    }
+
+    // file path under the nfs local root
+    pub fn local(&self, file: &PathBuf) -> Result<PathBuf> {
+        match self.cfg.nfs {
+            Some(ref v) => v.path(file),
+            None => Err(BAD_PROVIDER.into()),
+        }
+    }
+}
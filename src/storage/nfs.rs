@@ -0,0 +1,66 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use super::super::errors::Result;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Nfs {
+    #[serde(rename = "endpoint")]
+    pub end_point: String,
+    #[serde(rename = "localroot")]
+    pub local_root: String,
+}
+
+impl Nfs {
+    // only plain relative paths are allowed, so nothing escapes the local root
+    pub fn path<P: AsRef<Path>>(&self, key: P) -> Result<PathBuf> {
+        let key = key.as_ref();
+        for it in key.components() {
+            match it {
+                Component::Normal(_) => {}
+                _ => return Err(format!("bad storage key {}", key.display()).into()),
+            }
+        }
+        Ok(Path::new(&self.local_root).join(key))
+    }
+}
+
+impl super::Provider for Nfs {
+    fn name(&self) -> &'static str {
+        "nfs"
+    }
+
+    fn put(&self, key: &String, _content_type: &String, body: &[u8]) -> Result<()> {
+        let file = self.path(key)?;
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut fd = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(file)?;
+        fd.write_all(body)?;
+        Ok(())
+    }
+
+    fn get(&self, key: &String) -> Result<Vec<u8>> {
+        let mut fd = fs::File::open(self.path(key)?)?;
+        let mut buf = Vec::new();
+        fd.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn delete(&self, key: &String) -> Result<()> {
+        fs::remove_file(self.path(key)?)?;
+        Ok(())
+    }
+
+    fn exists(&self, key: &String) -> Result<bool> {
+        Ok(self.path(key)?.is_file())
+    }
+
+    fn url(&self, key: &String) -> Result<String> {
+        Ok(format!("{}/{}", self.end_point.trim_right_matches('/'), key))
+    }
+}
@@ -0,0 +1,240 @@
+use std::str::FromStr;
+
+use futures::{Future, Stream};
+use log;
+use rusoto_core::{
+    credential::{AwsCredentials, StaticProvider},
+    request::HttpClient,
+    Region,
+};
+use rusoto_s3::{
+    util::PreSignedRequest, AbortMultipartUploadRequest, CompleteMultipartUploadRequest,
+    CompletedMultipartUpload, CompletedPart, CreateMultipartUploadRequest, DeleteObjectRequest,
+    GetObjectError, GetObjectRequest, PutObjectRequest, S3 as S3Api, S3Client, UploadPartRequest,
+};
+
+use super::super::{env::Aws, errors::Result};
+
+// s3 requires at least 5MB for each part except the last one
+const PART_SIZE: usize = 1 << 23;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct S3 {
+    pub bucket: String,
+    pub region: String,
+    // custom endpoint for s3-compatible services, e.g. http://localhost:9000 for minio
+    pub endpoint: Option<String>,
+}
+
+impl S3 {
+    fn region(&self) -> Result<Region> {
+        Ok(match self.endpoint {
+            Some(ref v) => Region::Custom {
+                name: self.region.clone(),
+                endpoint: v.clone(),
+            },
+            None => match Region::from_str(&self.region) {
+                Ok(v) => v,
+                Err(e) => return Err(format!("bad s3 region {}: {:?}", self.region, e).into()),
+            },
+        })
+    }
+
+    pub fn open(&self, aws: &Aws) -> Result<Bucket> {
+        let region = self.region()?;
+        let dispatcher = match HttpClient::new() {
+            Ok(v) => v,
+            Err(e) => return Err(format!("{:?}", e).into()),
+        };
+        Ok(Bucket {
+            name: self.bucket.clone(),
+            client: S3Client::new_with(
+                dispatcher,
+                StaticProvider::new_minimal(
+                    aws.access_key_id.clone(),
+                    aws.secret_access_key.clone(),
+                ),
+                region.clone(),
+            ),
+            credentials: AwsCredentials::new(
+                aws.access_key_id.clone(),
+                aws.secret_access_key.clone(),
+                None,
+                None,
+            ),
+            region: region,
+        })
+    }
+}
+
+pub struct Bucket {
+    name: String,
+    region: Region,
+    credentials: AwsCredentials,
+    client: S3Client,
+}
+
+impl Bucket {
+    fn put_multipart(&self, key: &String, content_type: &String, body: &[u8]) -> Result<()> {
+        let upload_id = match self
+            .client
+            .create_multipart_upload(&CreateMultipartUploadRequest {
+                bucket: self.name.clone(),
+                key: key.clone(),
+                content_type: Some(content_type.clone()),
+                ..Default::default()
+            })
+            .sync()
+        {
+            Ok(v) => match v.upload_id {
+                Some(v) => v,
+                None => return Err(format!("empty upload id for {}", key).into()),
+            },
+            Err(e) => return Err(format!("{:?}", e).into()),
+        };
+
+        let mut parts = Vec::new();
+        for (i, chunk) in body.chunks(PART_SIZE).enumerate() {
+            let number = (i + 1) as i64;
+            match self
+                .client
+                .upload_part(&UploadPartRequest {
+                    bucket: self.name.clone(),
+                    key: key.clone(),
+                    upload_id: upload_id.clone(),
+                    part_number: number,
+                    body: Some(chunk.to_vec().into()),
+                    ..Default::default()
+                })
+                .sync()
+            {
+                Ok(v) => parts.push(CompletedPart {
+                    e_tag: v.e_tag,
+                    part_number: Some(number),
+                }),
+                Err(e) => {
+                    if let Err(e) = self
+                        .client
+                        .abort_multipart_upload(&AbortMultipartUploadRequest {
+                            bucket: self.name.clone(),
+                            key: key.clone(),
+                            upload_id: upload_id.clone(),
+                            ..Default::default()
+                        })
+                        .sync()
+                    {
+                        log::error!("abort multipart upload {} {:?}", key, e);
+                    }
+                    return Err(format!("{:?}", e).into());
+                }
+            }
+        }
+
+        if let Err(e) = self
+            .client
+            .complete_multipart_upload(&CompleteMultipartUploadRequest {
+                bucket: self.name.clone(),
+                key: key.clone(),
+                upload_id: upload_id,
+                multipart_upload: Some(CompletedMultipartUpload { parts: Some(parts) }),
+                ..Default::default()
+            })
+            .sync()
+        {
+            return Err(format!("{:?}", e).into());
+        }
+        Ok(())
+    }
+}
+
+impl super::Provider for Bucket {
+    fn name(&self) -> &'static str {
+        "s3"
+    }
+
+    fn put(&self, key: &String, content_type: &String, body: &[u8]) -> Result<()> {
+        if body.len() > PART_SIZE {
+            return self.put_multipart(key, content_type, body);
+        }
+        if let Err(e) = self
+            .client
+            .put_object(&PutObjectRequest {
+                bucket: self.name.clone(),
+                key: key.clone(),
+                content_type: Some(content_type.clone()),
+                body: Some(body.to_vec().into()),
+                ..Default::default()
+            })
+            .sync()
+        {
+            return Err(format!("{:?}", e).into());
+        }
+        Ok(())
+    }
+
+    fn get(&self, key: &String) -> Result<Vec<u8>> {
+        let it = match self
+            .client
+            .get_object(&GetObjectRequest {
+                bucket: self.name.clone(),
+                key: key.clone(),
+                ..Default::default()
+            })
+            .sync()
+        {
+            Ok(v) => v,
+            Err(e) => return Err(format!("{:?}", e).into()),
+        };
+        match it.body {
+            Some(body) => Ok(body.concat2().wait()?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn delete(&self, key: &String) -> Result<()> {
+        if let Err(e) = self
+            .client
+            .delete_object(&DeleteObjectRequest {
+                bucket: self.name.clone(),
+                key: key.clone(),
+                ..Default::default()
+            })
+            .sync()
+        {
+            return Err(format!("{:?}", e).into());
+        }
+        Ok(())
+    }
+
+    // a bodiless HEAD 404 can't be told from a 403 by this client, so ask for the first byte
+    // and let s3 answer with an error code
+    fn exists(&self, key: &String) -> Result<bool> {
+        match self
+            .client
+            .get_object(&GetObjectRequest {
+                bucket: self.name.clone(),
+                key: key.clone(),
+                range: Some("bytes=0-0".to_string()),
+                ..Default::default()
+            })
+            .sync()
+        {
+            Ok(_) => Ok(true),
+            Err(GetObjectError::NoSuchKey(_)) => Ok(false),
+            // an empty object has no first byte
+            Err(GetObjectError::Unknown(ref e)) if e.contains("<Code>InvalidRange</Code>") => {
+                Ok(true)
+            }
+            Err(e) => Err(format!("{:?}", e).into()),
+        }
+    }
+
+    // presigned GET url with the default expiry, so build it on every read
+    fn url(&self, key: &String) -> Result<String> {
+        Ok(GetObjectRequest {
+            bucket: self.name.clone(),
+            key: key.clone(),
+            ..Default::default()
+        }.get_presigned_url(&self.region, &self.credentials))
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use log;
+use rocket::response::content::Html;
+use serde_json::{self, Value};
+use tera::Tera;
+use toml;
+
+use super::{context::Context, errors::Result, orm::Connection as Db, settings};
+
+// settings key of the active theme, falls back to http.theme in config.toml
+pub const KEY: &'static str = "site.theme";
+pub const ROOT: &'static str = "themes";
+const MANIFEST: &'static str = "theme.toml";
+const VIEWS: &'static str = "views";
+const EXT: &'static str = ".html.tera";
+// shared by all themes, not a theme itself
+const GLOBAL: &'static str = "global";
+
+// themes/<name>/theme.toml
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Manifest {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    // views/<layout>.html.tera provided by the theme, checked when it's loaded
+    pub layouts: Vec<String>,
+    // default values exposed to the templates as theme.settings
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    pub fn load(name: &str) -> Result<Self> {
+        let mut file = fs::File::open(Path::new(ROOT).join(name).join(MANIFEST))?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        Ok(toml::from_slice(&buf)?)
+    }
+}
+
+// installed themes, by folder name
+pub fn list() -> Result<Vec<(String, Manifest)>> {
+    let mut items = Vec::new();
+    for it in fs::read_dir(ROOT)? {
+        let it = it?;
+        if !it.file_type()?.is_dir() {
+            continue;
+        }
+        let name = match it.file_name().into_string() {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if name == GLOBAL || name.starts_with('.') {
+            continue;
+        }
+        match Manifest::load(&name) {
+            Ok(v) => items.push((name, v)),
+            Err(e) => log::warn!("bad theme {}: {:?}", name, e),
+        }
+    }
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(items)
+}
+
+pub fn current(db: &Db, ctx: &Context) -> String {
+    match settings::get::<_, String>(db, &ctx.encryptor, &KEY.to_string()) {
+        Ok(v) => v,
+        Err(_) => ctx.config.http.theme.clone(),
+    }
+}
+
+pub fn assets(db: &Db, ctx: &Context) -> PathBuf {
+    Path::new(ROOT).join(current(db, ctx)).join("assets")
+}
+
+// a loaded theme, the manifest is parsed once together with the views
+pub struct Theme {
+    pub manifest: Manifest,
+    pub tera: Tera,
+}
+
+// one tera per theme, so that views refer to each other as "layout" or "forum/topics"
+// whatever the folder of the theme is
+pub struct Themes(RwLock<BTreeMap<String, Arc<Theme>>>);
+
+impl Themes {
+    pub fn new() -> Self {
+        Themes(RwLock::new(BTreeMap::new()))
+    }
+
+    // loaded on first use, so that a theme installed later needs no restart
+    pub fn get(&self, name: &str) -> Result<Arc<Theme>> {
+        if let Ok(items) = self.0.read() {
+            if let Some(it) = items.get(name) {
+                return Ok(Arc::clone(it));
+            }
+        }
+        self.load(name)
+    }
+
+    // reads the manifest and views from disk again and replaces the cached ones
+    pub fn load(&self, name: &str) -> Result<Arc<Theme>> {
+        let it = Arc::new(load(name)?);
+        match self.0.write() {
+            Ok(mut items) => {
+                items.insert(name.to_string(), Arc::clone(&it));
+            }
+            Err(e) => return Err(format!("{}", e).into()),
+        };
+        Ok(it)
+    }
+}
+
+fn views(root: &Path, dir: &Path, items: &mut Vec<(PathBuf, String)>) -> Result<()> {
+    for it in fs::read_dir(dir)? {
+        let it = it?.path();
+        if it.is_dir() {
+            views(root, &it, items)?;
+            continue;
+        }
+        let name = match it.strip_prefix(root).ok().and_then(|v| v.to_str()) {
+            Some(v) if v.ends_with(EXT) => v[..v.len() - EXT.len()].replace('\\', "/"),
+            _ => continue,
+        };
+        items.push((it, name));
+    }
+    Ok(())
+}
+
+fn load(name: &str) -> Result<Theme> {
+    let manifest = Manifest::load(name)?;
+    let root = Path::new(ROOT).join(name).join(VIEWS);
+    let mut items = Vec::new();
+    views(&root, &root, &mut items)?;
+    for it in manifest.layouts.iter() {
+        if !items.iter().any(|(_, v)| v == it) {
+            return Err(format!("theme {} has no layout {}", name, it).into());
+        }
+    }
+
+    // the same as the rocket_contrib templates
+    let mut tera = Tera::default();
+    tera.autoescape_on(vec![".html.tera", ".htm.tera", ".xml.tera"]);
+    if let Err(e) = tera.add_template_files(
+        items
+            .iter()
+            .map(|(path, name)| (path, Some(name.as_str())))
+            .collect(),
+    ) {
+        return Err(format!("bad theme {}: {}", name, e).into());
+    }
+    Ok(Theme {
+        manifest: manifest,
+        tera: tera,
+    })
+}
+
+pub fn render(db: &Db, ctx: &Context, name: &str, mut context: Value) -> Result<Html<String>> {
+    let id = current(db, ctx);
+    let theme = ctx.themes.get(&id)?;
+    if let Value::Object(ref mut items) = context {
+        items.insert(
+            "theme".to_string(),
+            json!({
+                "name": id,
+                "settings": serde_json::to_value(&theme.manifest.settings)?,
+            }),
+        );
+    }
+    match theme.tera.render(name, &context) {
+        Ok(v) => Ok(Html(v)),
+        Err(e) => Err(format!("render {}/{}: {}", id, name, e).into()),
+    }
+}
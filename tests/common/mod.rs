@@ -0,0 +1,91 @@
+use std::sync::{Arc, Mutex};
+
+use arche::{context::Context, env::Config, orm::migration};
+use toml;
+use uuid::Uuid;
+
+pub const LOCALE: &'static str = "en-US";
+
+// every in-memory sqlite pool is a new empty database
+#[cfg(feature = "sqlite")]
+const DATABASE: &'static str = r#"
+[database]
+file = ":memory:"
+"#;
+
+// needs an "arche_test" database on localhost
+#[cfg(feature = "postgresql")]
+const DATABASE: &'static str = r#"
+[database]
+host = "localhost"
+port = 5432
+name = "arche_test"
+user = "postgres"
+password = ""
+"#;
+
+#[cfg(feature = "mysql")]
+const DATABASE: &'static str = r#"
+[database]
+host = "localhost"
+port = 3306
+name = "arche_test"
+user = "root"
+password = ""
+"#;
+
+const CONFIG: &'static str = r#"
+name = "www.example.com"
+env = "development"
+secretkey = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
+
+[http]
+port = 8080
+workers = 1
+logging_level = "critical"
+theme = "bootstrap"
+limits = 65536
+
+[oauth]
+
+[cache]
+namespace = "test"
+
+[cache.memory]
+capacity = 1024
+
+[queue]
+name = "tasks"
+
+[queue.database]
+interval = 1
+
+[storage]
+
+[elasticsearch]
+hosts = []
+
+[aws]
+accesskeyid = ""
+secretaccesskey = ""
+"#;
+
+lazy_static! {
+    // tests run in parallel, but a database server must be migrated only once at a time
+    static ref MIGRATE: Mutex<()> = Mutex::new(());
+}
+
+pub fn open() -> Arc<Context> {
+    let cfg: Config = toml::from_str(&format!("{}{}", CONFIG, DATABASE)).unwrap();
+    let ctx = Context::new(&cfg).unwrap();
+    {
+        let _lock = MIGRATE.lock().unwrap();
+        migration::migrate(&ctx.db.get().unwrap()).unwrap();
+    }
+    Arc::new(ctx)
+}
+
+// the server databases are shared by every run
+pub fn unique() -> String {
+    Uuid::new_v4().simple().to_string()
+}
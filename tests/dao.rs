@@ -0,0 +1,105 @@
+// the insert paths must return the new ids on every backend
+extern crate arche;
+extern crate chrono;
+extern crate diesel;
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate serde_json;
+extern crate toml;
+extern crate uuid;
+
+mod common;
+
+use std::ops::Deref;
+
+use arche::{
+    i18n,
+    orm::schema::{jobs, logs, policies, users},
+    plugins::nut::{dao, models::Role},
+    queue, settings,
+};
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+
+#[test]
+fn insert_paths() {
+    let ctx = common::open();
+    let db = ctx.db.get().unwrap();
+    let db = db.deref();
+    let id = common::unique();
+
+    let email = format!("{}@example.com", id);
+    let (user, uid) =
+        dao::user::add_by_email(db, &"who".to_string(), &email, &"123456".to_string()).unwrap();
+    assert_eq!(
+        users::dsl::users
+            .select(users::dsl::id)
+            .filter(users::dsl::uid.eq(&uid))
+            .first::<i64>(db)
+            .unwrap(),
+        user
+    );
+
+    let nbf = Utc::now().naive_utc().date();
+    let exp = nbf + Duration::days(1);
+    let policy = dao::policy::apply(db, &user, &Role::Admin, &None, &nbf, &exp).unwrap();
+    assert_eq!(
+        policies::dsl::policies
+            .select(policies::dsl::user_id)
+            .filter(policies::dsl::id.eq(&policy))
+            .first::<i64>(db)
+            .unwrap(),
+        user
+    );
+    // applied again, the same row is updated
+    assert_eq!(
+        dao::policy::apply(db, &user, &Role::Admin, &None, &nbf, &exp).unwrap(),
+        policy
+    );
+
+    let log = dao::log::add(
+        db,
+        &user,
+        &"127.0.0.1".to_string(),
+        &id,
+        &"hi".to_string(),
+    ).unwrap();
+    assert_eq!(
+        logs::dsl::logs
+            .select(logs::dsl::user_id)
+            .filter(logs::dsl::id.eq(&log))
+            .first::<i64>(db)
+            .unwrap(),
+        user
+    );
+
+    let lang = common::LOCALE.to_string();
+    let code = format!("test.{}", id);
+    let locale = i18n::set(db, &lang, &code, &"a".to_string()).unwrap();
+    assert_eq!(i18n::set(db, &lang, &code, &"b".to_string()).unwrap(), locale);
+    assert_eq!(i18n::get(db, &lang, &code).unwrap(), "b");
+
+    for encrypt in &[false, true] {
+        let key = format!("test.{}.{}", id, encrypt);
+        let it = settings::set(db, &ctx.encryptor, &key, &"a", *encrypt).unwrap();
+        assert_eq!(
+            settings::set(db, &ctx.encryptor, &key, &"b", *encrypt).unwrap(),
+            it
+        );
+        assert_eq!(
+            settings::get::<_, String>(db, &ctx.encryptor, &key).unwrap(),
+            "b"
+        );
+    }
+
+    let job = queue::enqueue(db, "test", 1, &json!({ "id": id })).unwrap();
+    assert_eq!(
+        jobs::dsl::jobs
+            .select(jobs::dsl::type_)
+            .filter(jobs::dsl::id.eq(&job))
+            .first::<String>(db)
+            .unwrap(),
+        "test"
+    );
+}
@@ -0,0 +1,137 @@
+// for f in postgresql mysql sqlite; do cargo test --no-default-features --features $f; done
+extern crate arche;
+extern crate chrono;
+extern crate diesel;
+extern crate juniper;
+#[macro_use]
+extern crate lazy_static;
+extern crate serde_json;
+extern crate toml;
+extern crate uuid;
+
+mod common;
+
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use arche::{
+    context::Context,
+    graphql::{
+        context::Context as GraphQLContext, mutation::Mutation, query::Query, schema::Schema,
+    },
+    orm::{schema::jobs, PooledConnection},
+    plugins::nut::{dao, models::Role},
+};
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use serde_json::Value;
+
+fn execute(ctx: &Arc<Context>, token: &Option<String>, query: &str) -> Value {
+    let schema = Schema::new(Query, Mutation);
+    let (value, errors) = juniper::execute(
+        query,
+        None,
+        &schema,
+        &HashMap::new(),
+        &GraphQLContext {
+            home: "http://localhost:8080".to_string(),
+            locale: common::LOCALE.to_string(),
+            token: token.clone(),
+            client_ip: "127.0.0.1".to_string(),
+            request_id: "test".to_string(),
+            app: Arc::clone(ctx),
+            db: PooledConnection(ctx.db.get().unwrap()),
+        },
+    ).unwrap();
+    assert!(errors.is_empty(), "{} {:?}", query, errors);
+    serde_json::to_value(value).unwrap()
+}
+
+fn sign_in(ctx: &Arc<Context>, email: &str, password: &str) -> Option<String> {
+    execute(
+        ctx,
+        &None,
+        &format!(
+            r#"mutation {{ signInUserByEmail(email: "{}", password: "{}") {{ token }} }}"#,
+            email, password
+        ),
+    )["signInUserByEmail"]["token"]
+        .as_str()
+        .map(|v| v.to_string())
+}
+
+fn count_jobs(ctx: &Arc<Context>) -> i64 {
+    let db = ctx.db.get().unwrap();
+    jobs::dsl::jobs
+        .count()
+        .get_result::<i64>(db.deref())
+        .unwrap()
+}
+
+// needs an empty users table, so only on the throwaway sqlite database
+#[cfg(feature = "sqlite")]
+#[test]
+fn install() {
+    let ctx = common::open();
+    execute(
+        &ctx,
+        &None,
+        r#"mutation { install(name: "admin", email: "admin@example.com", password: "123456") { createdAt } }"#,
+    );
+    assert!(sign_in(&ctx, "admin@example.com", "123456").is_some());
+}
+
+#[test]
+fn resolvers() {
+    let ctx = common::open();
+    let id = common::unique();
+    let email = format!("{}@example.com", id);
+    let password = "123456".to_string();
+    {
+        let db = ctx.db.get().unwrap();
+        let db = db.deref();
+        let (user, _) =
+            dao::user::add_by_email(db, &"admin".to_string(), &email, &password).unwrap();
+        dao::user::confirm(db, &user).unwrap();
+        let nbf = Utc::now().naive_utc().date();
+        let exp = nbf + Duration::days(1);
+        dao::policy::apply(db, &user, &Role::Admin, &None, &nbf, &exp).unwrap();
+    }
+    let token = sign_in(&ctx, &email, &password);
+    assert!(token.is_some());
+
+    let code = format!("test.{}", id);
+    for code in &[
+        code.as_str(),
+        "nut.emails.user.confirm.subject",
+        "nut.emails.user.confirm.body",
+    ] {
+        execute(
+            &ctx,
+            &token,
+            &format!(
+                r#"mutation {{ updateLocale(code: "{}", message: "hi") {{ createdAt }} }}"#,
+                code
+            ),
+        );
+    }
+    let it = execute(
+        &ctx,
+        &None,
+        &format!(r#"{{ getLocale(code: "{}") {{ message }} }}"#, code),
+    );
+    assert_eq!(it["getLocale"]["message"], "hi");
+
+    // the confirm mail is a job row when the database queue is used
+    let jobs = count_jobs(&ctx);
+    execute(
+        &ctx,
+        &None,
+        &format!(
+            r#"mutation {{ signUpUser(name: "who", email: "who.{}@example.com", password: "123456") {{ createdAt }} }}"#,
+            id
+        ),
+    );
+    assert_eq!(count_jobs(&ctx), jobs + 1);
+}
@@ -0,0 +1,16 @@
+class CreateJobs < ActiveRecord::Migration[5.2]
+  def change
+    create_table :jobs do |t|
+      t.string :type, null: false, limit: 32
+      t.string :content_type, null: false, limit: 32
+      t.integer :priority, null: false, limit: 2
+      t.binary :payload, null: false
+      t.integer :attempts, null: false, limit: 2
+      t.datetime :run_at, null: false
+      t.text :last_error
+      t.timestamps
+    end
+    add_index :jobs, :type
+    add_index :jobs, %i[priority run_at]
+  end
+end
@@ -0,0 +1,6 @@
+class AddFailedAtToJobs < ActiveRecord::Migration[5.2]
+  def change
+    add_column :jobs, :failed_at, :datetime
+    add_index :jobs, :failed_at
+  end
+end
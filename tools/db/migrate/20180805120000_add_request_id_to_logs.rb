@@ -0,0 +1,6 @@
+class AddRequestIdToLogs < ActiveRecord::Migration[5.2]
+  def change
+    add_column :logs, :request_id, :string, limit: 36
+    add_index :logs, :request_id
+  end
+end
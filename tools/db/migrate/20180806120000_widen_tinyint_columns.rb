@@ -0,0 +1,19 @@
+class WidenTinyintColumns < ActiveRecord::Migration[5.2]
+  def up
+    change_column :cards, :position, :integer, null: false, limit: 2
+    change_column :caring_topics, :age, :integer, null: false, limit: 2
+    change_column :friend_links, :position, :integer, null: false, limit: 2
+    change_column :links, :x, :integer, null: false, limit: 2
+    change_column :links, :y, :integer, null: false, limit: 2
+    change_column :survey_fields, :sort, :integer, null: false, limit: 2
+  end
+
+  def down
+    change_column :cards, :position, :integer, null: false, limit: 1
+    change_column :caring_topics, :age, :integer, null: false, limit: 1
+    change_column :friend_links, :position, :integer, null: false, limit: 1
+    change_column :links, :x, :integer, null: false, limit: 1
+    change_column :links, :y, :integer, null: false, limit: 1
+    change_column :survey_fields, :sort, :integer, null: false, limit: 1
+  end
+end